@@ -1,94 +0,0 @@
-(() => {
-  var hljsGleamGrammar = (() => {
-    var b = (n, e) => () => (
-      e || n((e = { exports: {} }).exports, e),
-      e.exports
-    );
-    var o = b((l, c) => {
-      c.exports = function (n) {
-        let e = {
-            className: "keyword",
-            beginKeywords:
-              "as assert auto case const delegate derive echo else fn if implement import let macro opaque panic pub test todo type use",
-          },
-          a = {
-            className: "string",
-            variants: [{ begin: /"/, end: /"/ }],
-            contains: [n.BACKSLASH_ESCAPE],
-            relevance: 0,
-          },
-          t = {
-            className: "variable",
-            begin: "\\b[a-z][a-z0-9_]*\\b",
-            relevance: 0,
-          },
-          s = {
-            className: "comment",
-            begin: "\\b_[a-z][a-z0-9_]*\\b",
-            relevance: 0,
-          },
-          i = {
-            className: "number",
-            variants: [
-              { begin: "\\b0[bB](?:_?[01]+)+" },
-              { begin: "\\b0[oO](?:_?[0-7]+)+" },
-              { begin: "\\b0[xX](?:_?[0-9a-fA-F]+)+" },
-              { begin: "\\b\\d(?:_?\\d+)*(?:\\.(?:\\d(?:_?\\d+)*)*)?" },
-            ],
-            relevance: 0,
-          };
-        return {
-          name: "Gleam",
-          aliases: ["gleam"],
-          contains: [
-            n.C_LINE_COMMENT_MODE,
-            a,
-            {
-              begin: "<<",
-              end: ">>",
-              contains: [
-                {
-                  className: "keyword",
-                  beginKeywords:
-                    "binary bits bytes int float bit_string bit_array bits utf8 utf16 utf32 utf8_codepoint utf16_codepoint utf32_codepoint signed unsigned big little native unit size",
-                },
-                e,
-                a,
-                t,
-                s,
-                i,
-              ],
-              relevance: 10,
-            },
-            {
-              className: "function",
-              beginKeywords: "fn",
-              end: "\\(",
-              excludeEnd: !0,
-              contains: [
-                {
-                  className: "title",
-                  begin: "[a-z][a-z0-9_]*\\w*",
-                  relevance: 0,
-                },
-              ],
-            },
-            { className: "attribute", begin: "@", end: "\\(", excludeEnd: !0 },
-            e,
-            {
-              className: "title",
-              begin: "\\b[A-Z][A-Za-z0-9]*\\b",
-              relevance: 0,
-            },
-            { className: "operator", begin: "[+\\-*/%!=<>&|.]+", relevance: 0 },
-            t,
-            s,
-            i,
-          ],
-        };
-      };
-    });
-    return o();
-  })();
-  hljs.registerLanguage("gleam", hljsGleamGrammar);
-})();
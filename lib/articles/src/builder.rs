@@ -1,6 +1,6 @@
-use crate::Article;
-use chrono::DateTime;
-use chrono_tz::Tz;
+use crate::{Article, slug::slugify};
+use chrono::{DateTime, NaiveTime, Utc};
+use chrono_tz::{Tz, US::Pacific};
 
 const DEFAULT_TITLE: &str = "title-me";
 const DEFAULT_AUTHOR: &str = "sneakycrow";
@@ -11,9 +11,14 @@ where
     S: ToString,
 {
     title: Option<S>,
+    slug: Option<String>,
     content: Option<String>,
     date: Option<DateTime<Tz>>,
     author: Option<String>,
+    category: Option<String>,
+    tags: Vec<String>,
+    summary: Option<String>,
+    draft: bool,
 }
 
 impl<S> ArticleBuilder<S>
@@ -29,17 +34,39 @@ where
 
         let content = self.content.unwrap_or_default();
 
-        let pacific_time = self
-            .date
-            .unwrap_or(chrono::Utc::now().with_timezone(&chrono_tz::US::Pacific));
+        // new articles are dated by the day, midnight Pacific Time
+        let pacific_time = self.date.unwrap_or_else(|| {
+            let now = Utc::now().with_timezone(&Pacific);
+            now.date_naive()
+                .and_time(NaiveTime::MIN)
+                .and_local_timezone(Pacific)
+                .earliest()
+                .unwrap_or(now)
+        });
 
         let author = self.author.unwrap_or(DEFAULT_AUTHOR.to_string());
 
         Article {
             title,
+            slug: self.slug,
             content,
             date: pacific_time,
             author,
+            updated: None,
+            draft: self.draft,
+            publish_at: None,
+            category: self.category,
+            tags: self.tags,
+            series_key: None,
+            series_pos: None,
+            summary: self.summary,
+            description: None,
+            cover_image: None,
+            extras: Default::default(),
+            format: Default::default(),
+            toc: true,
+            source: None,
+            content_line: 1,
         }
     }
 
@@ -55,6 +82,12 @@ where
         self
     }
 
+    /// Adds an optional slug to the article, normalized into a url segment.
+    pub fn maybe_slug(mut self, slug: Option<String>) -> Self {
+        self.slug = slug.map(|s| slugify(&s)).filter(|s| !s.is_empty());
+        self
+    }
+
     /// Adds content to the article.
     pub fn content(mut self, content: String) -> Self {
         self.content = Some(content);
@@ -66,4 +99,28 @@ where
         self.content = content;
         self
     }
+
+    /// Adds an optional category to the article.
+    pub fn maybe_category(mut self, category: Option<String>) -> Self {
+        self.category = category;
+        self
+    }
+
+    /// Adds tags to the article.
+    pub fn tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Adds an optional summary to the article.
+    pub fn maybe_summary(mut self, summary: Option<String>) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Marks the article as a draft.
+    pub fn draft(mut self, draft: bool) -> Self {
+        self.draft = draft;
+        self
+    }
 }
@@ -0,0 +1,168 @@
+use crate::{
+    highlight,
+    render::{Attributes, escape},
+};
+use std::collections::BTreeSet;
+
+/// What the info string of a code fence says about the block
+///
+/// Parsed from info strings like `rust src/main.rs {hl_lines=[2, "4-6"] linenos}`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CodeInfo {
+    pub language: Option<String>,
+    /// File the code comes from, shown as the caption
+    pub filename: Option<String>,
+    /// Whether to number the lines
+    pub linenos: bool,
+    /// Number of the first line when numbering
+    pub line_start: usize,
+    /// Lines to emphasize, counted from 1 regardless of `line_start`
+    pub hl_lines: BTreeSet<usize>,
+}
+
+impl CodeInfo {
+    /// Parses the info string after the opening fence
+    pub fn parse(info: &str) -> Self {
+        let mut code = CodeInfo {
+            line_start: 1,
+            ..Default::default()
+        };
+
+        for (i, word) in words(info).into_iter().enumerate() {
+            if let Some(attributes) = word.strip_prefix('{') {
+                let attributes = attributes.strip_suffix('}').unwrap_or(attributes);
+                for attribute in words(&attributes.replace(',', " ")) {
+                    code.attribute(&attribute);
+                }
+            } else if word.contains('=') || word == "linenos" {
+                code.attribute(&word);
+            } else if i == 0 {
+                code.language = Some(word);
+            } else if code.filename.is_none() {
+                code.filename = Some(word);
+            }
+        }
+
+        code
+    }
+
+    /// Applies a single `key=value` or flag attribute, unknown ones are ignored
+    fn attribute(&mut self, attribute: &str) {
+        let (key, value) = attribute.split_once('=').unwrap_or((attribute, "true"));
+        let value = value.trim_matches(['"', '\'']);
+        match key.trim() {
+            "linenos" => self.linenos = value != "false",
+            "linenostart" => {
+                if let Ok(start) = value.parse() {
+                    self.linenos = true;
+                    self.line_start = start;
+                }
+            }
+            "hl_lines" => self.hl_lines = line_ranges(value),
+            "title" | "file" | "filename" => self.filename = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    /// Whether the block needs more than a plain `<pre>`
+    fn is_decorated(&self) -> bool {
+        self.filename.is_some() || self.linenos || !self.hl_lines.is_empty()
+    }
+}
+
+/// Splits an info string on whitespace, keeping `{...}`, `[...]` and quotes together
+pub(crate) fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0;
+    let mut quote = None;
+
+    for c in text.chars() {
+        match (c, quote) {
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('{' | '[', None) => depth += 1,
+            ('}' | ']', None) => depth -= 1,
+            (c, None) if c.is_whitespace() && depth <= 0 => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                continue;
+            }
+            _ => {}
+        }
+        word.push(c);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Parses a line list like `[2, 4]` or `["2-4", 7]` into line numbers
+fn line_ranges(value: &str) -> BTreeSet<usize> {
+    value
+        .trim_matches(['[', ']'])
+        .split([',', ' '])
+        .map(|part| part.trim().trim_matches(['"', '\'']))
+        .filter_map(|part| match part.split_once('-') {
+            Some((from, to)) => Some(from.trim().parse().ok()?..=to.trim().parse().ok()?),
+            None => part.parse().ok().map(|line| line..=line),
+        })
+        .flatten()
+        .collect()
+}
+
+/// Renders a single highlighted block, in a captioned figure when it has extras
+///
+/// The attributes go on the outermost element, the figure or the `<pre>`
+pub(crate) fn render_block(info: &CodeInfo, code: &str, attributes: &Attributes) -> String {
+    let language = info.language.as_deref().unwrap_or_default();
+    let highlighted = highlight::highlight(language, code.strip_suffix('\n').unwrap_or(code));
+    let class = match language {
+        "" => String::new(),
+        language => format!(" class=\"language-{}\"", escape(language)),
+    };
+
+    if !info.is_decorated() {
+        return format!(
+            "<pre{}><code{class}>{highlighted}\n</code></pre>",
+            attributes.to_html()
+        );
+    }
+
+    // One span per line, spans from the highlighter never cross lines
+    let mut lines = String::new();
+    for (i, line) in highlighted.split('\n').enumerate() {
+        let mut classes = String::from("line");
+        if info.hl_lines.contains(&(i + 1)) {
+            classes.push_str(" hl");
+        }
+        let number = if info.linenos {
+            format!(" data-line=\"{}\"", info.line_start + i)
+        } else {
+            String::new()
+        };
+        lines.push_str(&format!(
+            "<span class=\"{classes}\"{number}>{line}</span>\n"
+        ));
+    }
+
+    let caption = info
+        .filename
+        .as_ref()
+        .map(|f| format!("<figcaption>{}</figcaption>", escape(f)))
+        .unwrap_or_default();
+    let mut figure = attributes.clone();
+    figure.classes.insert(0, "code-block".to_string());
+    if info.linenos {
+        figure.classes.insert(1, "linenos".to_string());
+    }
+
+    format!(
+        "<figure{}>{caption}<pre><code{class}>{lines}</code></pre></figure>",
+        figure.to_html()
+    )
+}
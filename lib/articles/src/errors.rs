@@ -1,11 +1,63 @@
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ArticleError {
     #[error("Could not parse frontmatter: {0}")]
     FrontMatterParse(String),
-    #[error("Could not parse content")]
-    ContentParse,
+    #[error(
+        "Missing frontmatter, the file has to start with a `---` or `+++` line or a JSON object"
+    )]
+    MissingFrontMatter,
+    #[error("Frontmatter opened at line {line} is never closed with a `{delimiter}` line")]
+    UnclosedFrontMatter {
+        line: usize,
+        delimiter: &'static str,
+    },
+    #[error("Invalid frontmatter at line {line}, column {column}: {message}")]
+    FrontMatterSyntax {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    #[error("Could not parse content: {0}")]
+    ContentParse(String),
+    #[error("Could not render shortcode `{name}`: {message}")]
+    Shortcode { name: String, message: String },
+    #[error("Invalid math at line {line}: {message}")]
+    Math { line: usize, message: String },
+    #[error("Link at line {line} points at `{target}`, which isn't a known article")]
+    Link { line: usize, target: String },
     #[error("I/O error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("I/O error at {}: {source}", path.display())]
+    PathIO {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{}: {source}", path.display())]
+    InFile {
+        path: PathBuf,
+        #[source]
+        source: Box<ArticleError>,
+    },
+}
+
+impl ArticleError {
+    /// Creates a mapper attaching the path an I/O error happened at
+    pub fn io_at(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| Self::PathIO {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Attaches the article file an error happened in
+    pub fn in_file(self, path: &Path) -> Self {
+        Self::InFile {
+            path: path.to_path_buf(),
+            source: Box::new(self),
+        }
+    }
 }
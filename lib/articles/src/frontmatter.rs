@@ -0,0 +1,335 @@
+use crate::errors::ArticleError;
+use serde::{Deserialize, Serialize, de::IgnoredAny};
+use std::collections::BTreeMap;
+
+/// Syntax the frontmatter block of an article is written in
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// YAML between `---` lines
+    #[default]
+    Yaml,
+    /// TOML between `+++` lines, as used by Hugo
+    Toml,
+    /// A JSON object at the very start of the file
+    Json,
+}
+
+impl Format {
+    /// Line that opens and closes the block, JSON objects delimit themselves
+    pub fn delimiter(&self) -> Option<&'static str> {
+        match self {
+            Format::Yaml => Some("---"),
+            Format::Toml => Some("+++"),
+            Format::Json => None,
+        }
+    }
+
+    /// Detects the format from the first line of a file
+    fn detect(first_line: &str) -> Option<Self> {
+        match first_line.trim_end() {
+            "---" => Some(Format::Yaml),
+            "+++" => Some(Format::Toml),
+            line if line.starts_with('{') => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// The raw frontmatter of an article as it appears in the file
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub draft: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub series_pos: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_image: Option<String>,
+    /// Set to `false` to leave the table of contents off the page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toc: Option<bool>,
+    /// Any keys we don't model, kept so they survive a save
+    #[serde(flatten)]
+    pub extras: BTreeMap<String, serde_json::Value>,
+}
+
+impl Frontmatter {
+    /// Renders the frontmatter block in the given format, delimiters included
+    pub fn render(&self, format: Format) -> Result<String, ArticleError> {
+        let block = match format {
+            Format::Yaml => serde_yaml::to_string(self).map_err(|e| e.to_string()),
+            Format::Toml => toml::to_string(self).map_err(|e| e.to_string()),
+            Format::Json => serde_json::to_string_pretty(self)
+                .map(|json| format!("{json}\n"))
+                .map_err(|e| e.to_string()),
+        }
+        .map_err(ArticleError::FrontMatterParse)?;
+
+        Ok(match format.delimiter() {
+            Some(delimiter) => format!("{delimiter}\n{block}{delimiter}\n"),
+            None => format!("{block}\n"),
+        })
+    }
+}
+
+/// An article file split into its raw frontmatter and content
+pub struct RawArticle<'a> {
+    /// Syntax of the frontmatter block
+    pub format: Format,
+    /// The text of the block, without delimiter lines
+    pub frontmatter: &'a str,
+    /// Line of the file the frontmatter text starts on, 1-based
+    pub line: usize,
+    /// Everything after the frontmatter block
+    pub content: &'a str,
+}
+
+impl<'a> RawArticle<'a> {
+    /// Splits an article file into its frontmatter and content
+    ///
+    /// The file has to start with a `---` (YAML) or `+++` (TOML) line, or a
+    /// JSON object. A byte order mark and CRLF line endings are tolerated, and
+    /// a delimited block ends at the next line that is exactly its delimiter,
+    /// so `---` inside values or the content is left alone
+    pub fn split(source: &'a str) -> Result<Self, ArticleError> {
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+        let mut lines = source.split_inclusive('\n');
+
+        // The very first line decides the format
+        let opening = lines.next().ok_or(ArticleError::MissingFrontMatter)?;
+        let format = Format::detect(opening).ok_or(ArticleError::MissingFrontMatter)?;
+        let Some(delimiter) = format.delimiter() else {
+            return Self::split_json(source);
+        };
+
+        // Find the closing line, keeping track of where we are in the file
+        let start = opening.len();
+        let mut offset = start;
+        for line in lines {
+            if line.trim_end() == delimiter {
+                return Ok(Self {
+                    format,
+                    frontmatter: &source[start..offset],
+                    line: 2,
+                    content: &source[offset + line.len()..],
+                });
+            }
+            offset += line.len();
+        }
+
+        Err(ArticleError::UnclosedFrontMatter { line: 1, delimiter })
+    }
+
+    /// Splits off a leading JSON object, which ends where the object does
+    fn split_json(source: &'a str) -> Result<Self, ArticleError> {
+        let mut objects = serde_json::Deserializer::from_str(source).into_iter::<IgnoredAny>();
+        match objects.next() {
+            Some(Ok(_)) => {
+                let end = objects.byte_offset();
+                Ok(Self {
+                    format: Format::Json,
+                    frontmatter: &source[..end],
+                    line: 1,
+                    content: &source[end..],
+                })
+            }
+            Some(Err(e)) => Err(json_error(&e, 1)),
+            None => Err(ArticleError::MissingFrontMatter),
+        }
+    }
+
+    /// Parses the frontmatter, reporting errors at their line in the file
+    pub fn parse(&self) -> Result<Frontmatter, ArticleError> {
+        // An empty block is valid, the required fields are checked later
+        if self.frontmatter.trim().is_empty() {
+            return Ok(Frontmatter::default());
+        }
+
+        match self.format {
+            Format::Yaml => {
+                serde_yaml::from_str(self.frontmatter).map_err(|e| match e.location() {
+                    Some(location) => ArticleError::FrontMatterSyntax {
+                        line: self.line + location.line() - 1,
+                        column: location.column(),
+                        message: strip_locations(&e.to_string()),
+                    },
+                    None => ArticleError::FrontMatterParse(e.to_string()),
+                })
+            }
+            Format::Toml => {
+                let table: toml::Table =
+                    toml::from_str(self.frontmatter).map_err(|e| match e.span() {
+                        Some(span) => {
+                            let (line, column) = position(self.frontmatter, span.start);
+                            ArticleError::FrontMatterSyntax {
+                                line: self.line + line - 1,
+                                column,
+                                message: e.message().to_string(),
+                            }
+                        }
+                        None => ArticleError::FrontMatterParse(e.message().to_string()),
+                    })?;
+
+                // TOML has native dates, go through JSON so they end up as strings
+                Frontmatter::deserialize(toml_to_json(toml::Value::Table(table)))
+                    .map_err(|e| ArticleError::FrontMatterParse(e.to_string()))
+            }
+            Format::Json => {
+                serde_json::from_str(self.frontmatter).map_err(|e| json_error(&e, self.line))
+            }
+        }
+    }
+}
+
+/// Positions a JSON error in the file, the block starts at `line`
+fn json_error(e: &serde_json::Error, line: usize) -> ArticleError {
+    ArticleError::FrontMatterSyntax {
+        line: line + e.line() - 1,
+        column: e.column(),
+        message: strip_locations(&e.to_string()),
+    }
+}
+
+/// Line and column, both 1-based, of a byte offset in some text
+fn position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+/// Converts a TOML value into JSON, turning dates into their string form
+fn toml_to_json(value: toml::Value) -> serde_json::Value {
+    match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => serde_json::Value::from(i),
+        toml::Value::Float(f) => serde_json::Value::from(f),
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(dt) => serde_json::Value::String(dt.to_string()),
+        toml::Value::Array(values) => values.into_iter().map(toml_to_json).collect(),
+        toml::Value::Table(table) => serde_json::Value::Object(
+            table
+                .into_iter()
+                .map(|(k, v)| (k, toml_to_json(v)))
+                .collect(),
+        ),
+    }
+}
+
+/// Removes the ` at line N column M` markers from a parser error message,
+/// they are relative to the frontmatter block rather than the file
+fn strip_locations(message: &str) -> String {
+    let mut stripped = String::with_capacity(message.len());
+    let mut rest = message;
+    while let Some(index) = rest.find(" at line ") {
+        stripped.push_str(&rest[..index]);
+        let after = &rest[index + " at line ".len()..];
+        let after = after.trim_start_matches(|c: char| c.is_ascii_digit());
+        rest = after
+            .strip_prefix(" column ")
+            .map(|a| a.trim_start_matches(|c: char| c.is_ascii_digit()))
+            .unwrap_or(after);
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Format, RawArticle};
+    use crate::errors::ArticleError;
+
+    #[test]
+    fn leaves_delimiters_in_the_content_alone() {
+        let raw = RawArticle::split("---\ntitle: A\n---\nBody\n\n---\n\nMore\n").unwrap();
+        assert_eq!(raw.format, Format::Yaml);
+        assert_eq!(raw.frontmatter, "title: A\n");
+        assert_eq!(raw.content, "Body\n\n---\n\nMore\n");
+    }
+
+    #[test]
+    fn only_closes_on_a_whole_delimiter_line() {
+        let raw = RawArticle::split("---\ntitle: a --- b\n----\n---\nBody").unwrap();
+        assert_eq!(raw.frontmatter, "title: a --- b\n----\n");
+        assert_eq!(raw.content, "Body");
+    }
+
+    #[test]
+    fn skips_a_byte_order_mark() {
+        let raw = RawArticle::split("\u{feff}+++\ntitle = \"A\"\n+++\nBody").unwrap();
+        assert_eq!(raw.format, Format::Toml);
+        assert_eq!(raw.frontmatter, "title = \"A\"\n");
+        assert_eq!(raw.content, "Body");
+    }
+
+    #[test]
+    fn reads_crlf_delimiters() {
+        let raw = RawArticle::split("---\r\ntitle: A\r\n---\r\nBody\r\n").unwrap();
+        assert_eq!(raw.frontmatter, "title: A\r\n");
+        assert_eq!(raw.content, "Body\r\n");
+        assert_eq!(raw.parse().unwrap().title.as_deref(), Some("A"));
+    }
+
+    #[test]
+    fn splits_off_a_json_object() {
+        let raw = RawArticle::split("{\"title\": \"A {b}\"}\nBody").unwrap();
+        assert_eq!(raw.format, Format::Json);
+        assert_eq!(raw.frontmatter, "{\"title\": \"A {b}\"}");
+        assert_eq!(raw.content, "\nBody");
+    }
+
+    #[test]
+    fn reports_a_missing_closing_delimiter() {
+        let error = RawArticle::split("---\ntitle: A\nBody\n").err().unwrap();
+        assert!(matches!(
+            error,
+            ArticleError::UnclosedFrontMatter {
+                line: 1,
+                delimiter: "---"
+            }
+        ));
+    }
+
+    #[test]
+    fn reports_a_missing_frontmatter() {
+        let error = RawArticle::split("# Title\n").err().unwrap();
+        assert!(matches!(error, ArticleError::MissingFrontMatter));
+    }
+
+    #[test]
+    fn reports_yaml_errors_at_their_place_in_the_file() {
+        let raw = RawArticle::split("---\ntitle: A\ntags: a: b\n---\nBody").unwrap();
+        let error = raw.parse().unwrap_err();
+        let ArticleError::FrontMatterSyntax {
+            line,
+            column,
+            message,
+        } = error
+        else {
+            panic!("unexpected error: {error}");
+        };
+        // the value of `tags`, on the third line of the file
+        assert_eq!((line, column), (3, 7));
+        assert!(!message.contains(" at line "), "{message}");
+    }
+}
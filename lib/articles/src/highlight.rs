@@ -0,0 +1,146 @@
+mod languages;
+mod markup;
+
+use crate::render::escape_into;
+use languages::Language;
+
+/// File name of the generated stylesheet, relative to the assets directory
+pub const THEME_FILE: &str = "highlight.css";
+
+/// Prefix of the classes put on highlighted spans
+const CLASS_PREFIX: &str = "hl-";
+
+/// Kinds of tokens the highlighter tells apart, each gets its own class
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token {
+    Comment,
+    String,
+    Number,
+    Literal,
+    Keyword,
+    Type,
+    Function,
+    Macro,
+    Variable,
+    Attribute,
+    Section,
+    Tag,
+    Template,
+}
+
+impl Token {
+    /// Every token kind, in the order the theme is written
+    const ALL: [Token; 13] = [
+        Token::Comment,
+        Token::String,
+        Token::Number,
+        Token::Literal,
+        Token::Keyword,
+        Token::Type,
+        Token::Function,
+        Token::Macro,
+        Token::Variable,
+        Token::Attribute,
+        Token::Section,
+        Token::Tag,
+        Token::Template,
+    ];
+
+    /// Class name of the token, without the prefix
+    pub fn name(&self) -> &'static str {
+        match self {
+            Token::Comment => "comment",
+            Token::String => "string",
+            Token::Number => "number",
+            Token::Literal => "literal",
+            Token::Keyword => "keyword",
+            Token::Type => "type",
+            Token::Function => "function",
+            Token::Macro => "macro",
+            Token::Variable => "variable",
+            Token::Attribute => "attribute",
+            Token::Section => "section",
+            Token::Tag => "tag",
+            Token::Template => "template",
+        }
+    }
+
+    /// Color and extra declarations of the token in the theme
+    fn style(&self) -> (&'static str, &'static str) {
+        match self {
+            Token::Comment => ("#6272a4", "font-style: italic;"),
+            Token::String => ("#f1fa8c", ""),
+            Token::Number | Token::Literal => ("#bd93f9", ""),
+            Token::Keyword | Token::Tag => ("#ff79c6", ""),
+            Token::Type => ("#8be9fd", "font-style: italic;"),
+            Token::Function => ("#50fa7b", ""),
+            Token::Macro | Token::Section => ("#8be9fd", ""),
+            Token::Variable => ("#ffb86c", ""),
+            Token::Attribute => ("#50fa7b", "font-style: italic;"),
+            Token::Template => ("#ffb86c", ""),
+        }
+    }
+}
+
+/// Highlights a block of code, returning escaped HTML with classed spans
+///
+/// Unknown languages are escaped without any highlighting
+pub fn highlight(language: &str, code: &str) -> String {
+    let mut html = Html::default();
+    match Language::find(language) {
+        Some(Language::Code(syntax)) => syntax.highlight(code, &mut html),
+        Some(Language::Markup(syntax)) => syntax.highlight(code, &mut html),
+        None => html.text(code),
+    }
+    html.0
+}
+
+/// The stylesheet coloring highlighted spans
+pub fn theme_css() -> String {
+    let mut css =
+        String::from("/* generated by the site build, edit the theme in articles::highlight */\n");
+    for token in Token::ALL {
+        let (color, extra) = token.style();
+        css.push_str(&format!(
+            "\n.{CLASS_PREFIX}{} {{\n    color: {color};{}\n}}\n",
+            token.name(),
+            if extra.is_empty() {
+                String::new()
+            } else {
+                format!("\n    {extra}")
+            }
+        ));
+    }
+    css
+}
+
+/// Highlighted output, spans never cross lines so lines can be split later
+#[derive(Default)]
+pub(crate) struct Html(String);
+
+impl Html {
+    /// Writes plain text
+    pub fn text(&mut self, text: &str) {
+        escape_into(&mut self.0, text);
+    }
+
+    /// Writes text as a token, or plain text if there's no token
+    pub fn token(&mut self, token: Option<Token>, text: &str) {
+        let Some(token) = token else {
+            return self.text(text);
+        };
+
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.0.push('\n');
+            }
+            if line.is_empty() {
+                continue;
+            }
+            self.0
+                .push_str(&format!("<span class=\"{CLASS_PREFIX}{}\">", token.name()));
+            escape_into(&mut self.0, line);
+            self.0.push_str("</span>");
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use super::{Html, Token, markup::Markup};
+
+/// A language the highlighter knows how to tokenize
+pub(crate) enum Language {
+    Code(&'static Syntax),
+    Markup(&'static Markup),
+}
+
+impl Language {
+    /// Looks up a language by the name used in a code fence
+    pub fn find(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase();
+        let code = [
+            &RUST,
+            &GLEAM,
+            &JAVASCRIPT,
+            &SQL,
+            &TOML,
+            &YAML,
+            &JSON,
+            &SHELL,
+            &CSS,
+            &MARKDOWN,
+        ];
+        let markup = [
+            &super::markup::HTML,
+            &super::markup::SVELTE,
+            &super::markup::HANDLEBARS,
+        ];
+
+        code.into_iter()
+            .find(|s| s.names.contains(&name.as_str()))
+            .map(Language::Code)
+            .or_else(|| {
+                markup
+                    .into_iter()
+                    .find(|m| m.names.contains(&name.as_str()))
+                    .map(Language::Markup)
+            })
+    }
+}
+
+/// Lexical rules of a programming or config language
+pub(crate) struct Syntax {
+    /// Names and aliases used in code fences
+    pub names: &'static [&'static str],
+    pub line_comments: &'static [&'static str],
+    pub block_comment: Option<(&'static str, &'static str)>,
+    pub quotes: &'static [char],
+    /// Backslashes don't escape inside single quotes, as in shells
+    pub raw_single_quotes: bool,
+    pub keywords: &'static [&'static str],
+    pub literals: &'static [&'static str],
+    pub types: &'static [&'static str],
+    /// Keywords, literals and types match regardless of case
+    pub ignore_case: bool,
+    /// Capitalized identifiers are types
+    pub capitalized_types: bool,
+    /// `name!` is a macro and `'a` a lifetime, as in Rust
+    pub rust_like: bool,
+    /// `$name` and `${name}` are variables
+    pub variables: bool,
+    /// `@name` is a keyword, as in CSS at-rules and decorators
+    pub at_keywords: bool,
+    /// `[section]` lines are headers
+    pub sections: bool,
+    /// Keys of `key = value` and `key: value` pairs are attributes
+    pub keys: bool,
+    /// Identifiers may contain `-`
+    pub dashed_identifiers: bool,
+    /// Lines starting with `#` are headings, fences are comments, inline code is a string
+    pub markdown: bool,
+}
+
+impl Syntax {
+    const DEFAULT: Syntax = Syntax {
+        names: &[],
+        line_comments: &[],
+        block_comment: None,
+        quotes: &[],
+        raw_single_quotes: false,
+        keywords: &[],
+        literals: &[],
+        types: &[],
+        ignore_case: false,
+        capitalized_types: false,
+        rust_like: false,
+        variables: false,
+        at_keywords: false,
+        sections: false,
+        keys: false,
+        dashed_identifiers: false,
+        markdown: false,
+    };
+
+    /// Tokenizes code and writes the highlighted HTML
+    pub fn highlight(&self, code: &str, html: &mut Html) {
+        let mut i = 0;
+        while i < code.len() {
+            let rest = &code[i..];
+            let line_start = code[..i]
+                .rfind('\n')
+                .is_none_or(|n| code[n + 1..i].trim().is_empty());
+            let (token, len) = self.next_token(code, i, rest, line_start);
+            html.token(token, &rest[..len]);
+            i += len;
+        }
+    }
+
+    /// Finds the token at the current position and its length in bytes
+    fn next_token(
+        &self,
+        code: &str,
+        i: usize,
+        rest: &str,
+        line_start: bool,
+    ) -> (Option<Token>, usize) {
+        let c = rest.chars().next().unwrap_or_default();
+        let previous = code[..i].chars().next_back();
+
+        // Markdown is mostly prose, only headings, fences and inline code stand out
+        if self.markdown {
+            if line_start && c == '#' {
+                return (Some(Token::Section), line_len(rest));
+            }
+            if line_start && (rest.starts_with("```") || rest.starts_with("~~~")) {
+                return (Some(Token::Comment), line_len(rest));
+            }
+            if c == '`' {
+                return (Some(Token::String), quoted_len(rest, '`', false));
+            }
+            return (None, c.len_utf8());
+        }
+
+        // Comments, a marker only counts when it doesn't continue a word
+        let comment_allowed = previous.is_none_or(|p| !(p.is_alphanumeric() || "$:/-".contains(p)));
+        if comment_allowed && self.line_comments.iter().any(|m| rest.starts_with(m)) {
+            return (Some(Token::Comment), line_len(rest));
+        }
+        if let Some((open, close)) = self.block_comment
+            && rest.starts_with(open)
+        {
+            let len = rest[open.len()..]
+                .find(close)
+                .map_or(rest.len(), |end| open.len() + end + close.len());
+            return (Some(Token::Comment), len);
+        }
+
+        // Rust char literals and lifetimes share the quote
+        if self.rust_like && c == '\'' {
+            return match char_literal_len(rest) {
+                Some(len) => (Some(Token::String), len),
+                None => (Some(Token::Type), 1 + ident_len(&rest[1..], false)),
+            };
+        }
+
+        // Strings, which may be the key of a JSON or YAML pair
+        if self.quotes.contains(&c) {
+            let len = quoted_len(rest, c, c != '\'' || !self.raw_single_quotes);
+            let token = if self.keys && rest[len..].trim_start_matches(' ').starts_with(':') {
+                Token::Attribute
+            } else {
+                Token::String
+            };
+            return (Some(token), len);
+        }
+
+        // Section headers
+        if self.sections && line_start && c == '[' {
+            return (Some(Token::Section), line_len(rest));
+        }
+
+        // Variables and at-rules
+        if self.variables && c == '$' {
+            if rest[1..].starts_with('{') {
+                let len = rest.find('}').map_or(rest.len(), |end| end + 1);
+                return (Some(Token::Variable), len);
+            }
+            let len = ident_len(&rest[1..], self.dashed_identifiers);
+            if len > 0 {
+                return (Some(Token::Variable), 1 + len);
+            }
+        }
+        if self.at_keywords && c == '@' {
+            let len = ident_len(&rest[1..], true);
+            if len > 0 {
+                return (Some(Token::Keyword), 1 + len);
+            }
+        }
+
+        // Numbers, including suffixes and units like `10u8` or `2px`
+        let after_word = previous.is_some_and(|p| p.is_alphanumeric() || p == '_');
+        if c.is_ascii_digit() && !after_word {
+            return (Some(Token::Number), number_len(rest));
+        }
+
+        // Identifiers, classified by what they are and what follows them
+        if (c.is_alphabetic() || c == '_') && !after_word {
+            let len = ident_len(rest, self.dashed_identifiers);
+            let word = &rest[..len];
+            let after = &rest[len..];
+            return (self.classify(word, after, line_start), len);
+        }
+
+        (None, c.len_utf8())
+    }
+
+    /// Token kind of an identifier
+    fn classify(&self, word: &str, after: &str, line_start: bool) -> Option<Token> {
+        let matches = |list: &[&str]| {
+            list.iter().any(|w| {
+                if self.ignore_case {
+                    w.eq_ignore_ascii_case(word)
+                } else {
+                    *w == word
+                }
+            })
+        };
+        let next = after.trim_start_matches([' ', '\t']).chars().next();
+
+        if self.keys && line_start && matches!(next, Some(':' | '=')) {
+            Some(Token::Attribute)
+        } else if matches(self.keywords) {
+            Some(Token::Keyword)
+        } else if matches(self.literals) {
+            Some(Token::Literal)
+        } else if matches(self.types) {
+            Some(Token::Type)
+        } else if self.rust_like && after.starts_with('!') && !after.starts_with("!=") {
+            Some(Token::Macro)
+        } else if self.capitalized_types && word.starts_with(|c: char| c.is_uppercase()) {
+            Some(Token::Type)
+        } else if next == Some('(') {
+            Some(Token::Function)
+        } else {
+            None
+        }
+    }
+}
+
+/// Length of the rest of the line, without the line break
+fn line_len(text: &str) -> usize {
+    text.find('\n').unwrap_or(text.len())
+}
+
+/// Length of an identifier at the start of the text
+fn ident_len(text: &str, dashed: bool) -> usize {
+    text.find(|c: char| !(c.is_alphanumeric() || c == '_' || (dashed && c == '-')))
+        .unwrap_or(text.len())
+}
+
+/// Length of a number at the start of the text, a `.` only counts before a digit
+fn number_len(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut len = 0;
+    while len < bytes.len() {
+        let b = bytes[len];
+        let decimal_point = b == b'.' && bytes.get(len + 1).is_some_and(u8::is_ascii_digit);
+        if b.is_ascii_alphanumeric() || b == b'_' || decimal_point {
+            len += 1;
+        } else {
+            break;
+        }
+    }
+    len
+}
+
+/// Length of a quoted string, running to the end of the text when unclosed
+fn quoted_len(text: &str, quote: char, escapes: bool) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        if escapes && c == '\\' {
+            chars.next();
+        } else if c == quote {
+            return i + c.len_utf8();
+        }
+    }
+    text.len()
+}
+
+/// Length of a Rust char literal like `'a'` or `'\n'`, `None` for a lifetime
+fn char_literal_len(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().skip(1);
+    match chars.next()? {
+        (_, '\\') => text[2..].find('\'').map(|end| end + 3),
+        (_, c) => match chars.next()? {
+            (i, '\'') if c != '\'' => Some(i + 1),
+            _ => None,
+        },
+    }
+}
+
+pub(crate) static RUST: Syntax = Syntax {
+    names: &["rust", "rs"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"'],
+    keywords: &[
+        "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum",
+        "extern", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut",
+        "pub", "ref", "return", "self", "Self", "static", "struct", "super", "trait", "type",
+        "unsafe", "use", "where", "while",
+    ],
+    literals: &["true", "false"],
+    types: &[
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+        "f32", "f64", "bool", "char", "str",
+    ],
+    capitalized_types: true,
+    rust_like: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static GLEAM: Syntax = Syntax {
+    names: &["gleam"],
+    line_comments: &["//"],
+    quotes: &['"'],
+    keywords: &[
+        "as", "assert", "case", "const", "external", "fn", "if", "import", "let", "opaque",
+        "panic", "pub", "todo", "type", "use",
+    ],
+    literals: &["True", "False", "Nil"],
+    capitalized_types: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static JAVASCRIPT: Syntax = Syntax {
+    names: &[
+        "javascript",
+        "js",
+        "jsx",
+        "typescript",
+        "ts",
+        "tsx",
+        "mjs",
+        "cjs",
+    ],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\'', '`'],
+    keywords: &[
+        "as",
+        "async",
+        "await",
+        "break",
+        "case",
+        "catch",
+        "class",
+        "const",
+        "continue",
+        "declare",
+        "default",
+        "delete",
+        "do",
+        "else",
+        "enum",
+        "export",
+        "extends",
+        "finally",
+        "for",
+        "from",
+        "function",
+        "if",
+        "implements",
+        "import",
+        "in",
+        "instanceof",
+        "interface",
+        "let",
+        "new",
+        "of",
+        "private",
+        "protected",
+        "public",
+        "readonly",
+        "return",
+        "static",
+        "super",
+        "switch",
+        "this",
+        "throw",
+        "try",
+        "type",
+        "typeof",
+        "var",
+        "void",
+        "while",
+        "yield",
+    ],
+    literals: &["true", "false", "null", "undefined", "NaN"],
+    types: &[
+        "string", "number", "boolean", "any", "unknown", "never", "object",
+    ],
+    capitalized_types: true,
+    at_keywords: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static SQL: Syntax = Syntax {
+    names: &["sql", "psql", "postgres", "postgresql", "sqlite"],
+    line_comments: &["--"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['\'', '"'],
+    keywords: &[
+        "add",
+        "after",
+        "alter",
+        "and",
+        "as",
+        "asc",
+        "before",
+        "begin",
+        "by",
+        "cascade",
+        "case",
+        "check",
+        "column",
+        "constraint",
+        "create",
+        "declare",
+        "default",
+        "delete",
+        "desc",
+        "distinct",
+        "drop",
+        "each",
+        "else",
+        "end",
+        "execute",
+        "exists",
+        "for",
+        "foreign",
+        "from",
+        "function",
+        "group",
+        "having",
+        "if",
+        "in",
+        "index",
+        "inner",
+        "insert",
+        "into",
+        "is",
+        "join",
+        "key",
+        "language",
+        "left",
+        "limit",
+        "listen",
+        "new",
+        "not",
+        "notify",
+        "offset",
+        "old",
+        "on",
+        "or",
+        "order",
+        "outer",
+        "perform",
+        "primary",
+        "procedure",
+        "references",
+        "replace",
+        "return",
+        "returning",
+        "returns",
+        "right",
+        "row",
+        "select",
+        "set",
+        "table",
+        "then",
+        "trigger",
+        "type",
+        "union",
+        "unique",
+        "update",
+        "using",
+        "values",
+        "when",
+        "where",
+        "with",
+    ],
+    literals: &["true", "false", "null"],
+    types: &[
+        "bigint",
+        "bigserial",
+        "boolean",
+        "bytea",
+        "char",
+        "date",
+        "float",
+        "int",
+        "integer",
+        "interval",
+        "json",
+        "jsonb",
+        "numeric",
+        "real",
+        "serial",
+        "smallint",
+        "text",
+        "time",
+        "timestamp",
+        "timestamptz",
+        "uuid",
+        "varchar",
+    ],
+    ignore_case: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static TOML: Syntax = Syntax {
+    names: &["toml"],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    literals: &["true", "false"],
+    sections: true,
+    keys: true,
+    dashed_identifiers: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static YAML: Syntax = Syntax {
+    names: &["yaml", "yml"],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    literals: &["true", "false", "null", "yes", "no", "on", "off"],
+    keys: true,
+    dashed_identifiers: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static JSON: Syntax = Syntax {
+    names: &["json", "jsonc"],
+    line_comments: &["//"],
+    quotes: &['"'],
+    literals: &["true", "false", "null"],
+    keys: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static SHELL: Syntax = Syntax {
+    names: &["shell", "sh", "bash", "zsh", "console", "shellsession"],
+    line_comments: &["#"],
+    quotes: &['"', '\''],
+    keywords: &[
+        "case", "do", "done", "elif", "else", "esac", "export", "fi", "for", "function", "if",
+        "in", "local", "return", "then", "while",
+    ],
+    raw_single_quotes: true,
+    variables: true,
+    dashed_identifiers: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static CSS: Syntax = Syntax {
+    names: &["css", "scss", "sass", "less"],
+    line_comments: &["//"],
+    block_comment: Some(("/*", "*/")),
+    quotes: &['"', '\''],
+    literals: &["inherit", "initial", "none", "auto"],
+    variables: true,
+    at_keywords: true,
+    keys: true,
+    dashed_identifiers: true,
+    ..Syntax::DEFAULT
+};
+
+pub(crate) static MARKDOWN: Syntax = Syntax {
+    names: &["markdown", "md"],
+    markdown: true,
+    ..Syntax::DEFAULT
+};
@@ -0,0 +1,175 @@
+use super::{
+    Html, Token,
+    languages::{CSS, JAVASCRIPT, Syntax},
+};
+
+/// Lexical rules of an HTML-like template language
+pub(crate) struct Markup {
+    /// Names and aliases used in code fences
+    pub names: &'static [&'static str],
+    /// `{expression}` blocks are JavaScript, as in Svelte
+    pub braces: bool,
+    /// `{{expression}}` blocks are template tags, as in Handlebars
+    pub mustaches: bool,
+}
+
+pub(crate) static HTML: Markup = Markup {
+    names: &["html", "xml", "svg", "vue"],
+    braces: false,
+    mustaches: false,
+};
+
+pub(crate) static SVELTE: Markup = Markup {
+    names: &["svelte"],
+    braces: true,
+    mustaches: false,
+};
+
+pub(crate) static HANDLEBARS: Markup = Markup {
+    names: &["handlebars", "hbs", "mustache"],
+    braces: false,
+    mustaches: true,
+};
+
+impl Markup {
+    /// Tokenizes markup and writes the highlighted HTML
+    pub fn highlight(&self, code: &str, html: &mut Html) {
+        let mut rest = code;
+        while let Some(c) = rest.chars().next() {
+            let len = if rest.starts_with("<!--") {
+                let len = delimited_len(rest, "-->");
+                html.token(Some(Token::Comment), &rest[..len]);
+                len
+            } else if self.mustaches && rest.starts_with("{{!") {
+                let close = if rest.starts_with("{{!--") {
+                    "--}}"
+                } else {
+                    "}}"
+                };
+                let len = delimited_len(rest, close);
+                html.token(Some(Token::Comment), &rest[..len]);
+                len
+            } else if self.mustaches && rest.starts_with("{{") {
+                let len = delimited_len(rest, "}}");
+                html.token(Some(Token::Template), &rest[..len]);
+                len
+            } else if self.braces && c == '{' {
+                self.expression(rest, html)
+            } else if c == '<' && rest[1..].starts_with(|c: char| c.is_alphabetic() || c == '/') {
+                self.tag(rest, html)
+            } else if c == '&' && entity_len(rest).is_some() {
+                let len = entity_len(rest).unwrap_or(1);
+                html.token(Some(Token::Literal), &rest[..len]);
+                len
+            } else {
+                html.text(&rest[..c.len_utf8()]);
+                c.len_utf8()
+            };
+            rest = &rest[len..];
+        }
+    }
+
+    /// Writes a tag with its attributes, and the script or style it contains
+    fn tag(&self, text: &str, html: &mut Html) -> usize {
+        let closing = text.starts_with("</");
+        let name_start = if closing { 2 } else { 1 };
+        let name_len = text[name_start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == ':' || c == '.'))
+            .unwrap_or(text.len() - name_start);
+        let name = &text[name_start..name_start + name_len];
+        html.text(&text[..name_start]);
+        html.token(Some(Token::Tag), name);
+
+        // Attributes up to the end of the tag
+        let mut i = name_start + name_len;
+        while let Some(c) = text[i..].chars().next() {
+            let rest = &text[i..];
+            if c == '>' || rest.starts_with("/>") {
+                let end = if c == '>' { 1 } else { 2 };
+                html.text(&rest[..end]);
+                i += end;
+                break;
+            }
+            i += if c == '"' || c == '\'' {
+                let len = rest[1..].find(c).map_or(rest.len(), |end| end + 2);
+                html.token(Some(Token::String), &rest[..len]);
+                len
+            } else if self.braces && c == '{' {
+                self.expression(rest, html)
+            } else if c.is_alphabetic() || c == '_' || c == ':' || c == '@' {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || "=>/\"'".contains(c))
+                    .unwrap_or(rest.len());
+                html.token(Some(Token::Attribute), &rest[..len]);
+                len
+            } else {
+                html.text(&rest[..c.len_utf8()]);
+                c.len_utf8()
+            };
+        }
+
+        // Scripts and styles are highlighted in their own language
+        let embedded: Option<&Syntax> = match name.to_ascii_lowercase().as_str() {
+            "script" if !closing => Some(&JAVASCRIPT),
+            "style" if !closing => Some(&CSS),
+            _ => None,
+        };
+        if let Some(syntax) = embedded {
+            let body = &text[i..];
+            let end = body
+                .to_ascii_lowercase()
+                .find(&format!("</{}", name.to_ascii_lowercase()))
+                .unwrap_or(body.len());
+            syntax.highlight(&body[..end], html);
+            i += end;
+        }
+
+        i
+    }
+
+    /// Writes a `{...}` expression, its content highlighted as JavaScript
+    fn expression(&self, text: &str, html: &mut Html) -> usize {
+        let mut depth = 0;
+        let mut end = text.len();
+        for (i, c) in text.char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = i;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        html.token(Some(Token::Template), "{");
+        JAVASCRIPT.highlight(&text[1..end], html);
+        if end < text.len() {
+            html.token(Some(Token::Template), "}");
+            end + 1
+        } else {
+            end
+        }
+    }
+}
+
+/// Length up to and including the closing marker, or the whole text
+fn delimited_len(text: &str, close: &str) -> usize {
+    text.find(close).map_or(text.len(), |end| end + close.len())
+}
+
+/// Length of a character reference like `&amp;` or `&#8212;`
+fn entity_len(text: &str) -> Option<usize> {
+    let end = text[1..].find(';')? + 1;
+    let name = &text[1..end];
+    let valid = !name.is_empty()
+        && name.len() <= 10
+        && name
+            .trim_start_matches('#')
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric());
+    valid.then_some(end + 1)
+}
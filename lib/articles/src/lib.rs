@@ -1,27 +1,94 @@
 pub mod builder;
+pub mod code;
 pub mod errors;
+pub mod frontmatter;
+pub mod highlight;
+pub mod reading;
+pub mod render;
+pub mod series;
+pub mod slug;
 pub use builder::ArticleBuilder;
-use chrono::{DateTime, Datelike, NaiveDate, TimeZone};
+use chrono::{
+    DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc,
+};
 use chrono_tz::{Tz, US::Pacific};
 use errors::ArticleError;
+use frontmatter::{Format, Frontmatter, RawArticle};
 use serde::Serialize;
-use std::{fs, path::PathBuf};
+pub use series::Series;
+use slug::slugify;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 const DEFAULT_AUTHOR: &str = "sneakycrow";
 
-#[derive(Serialize)]
-struct Frontmatter<'a> {
-    title: &'a str,
-    author: Option<&'a str>,
-    date: &'a str,
-}
+/// Timestamps without an offset, read as Pacific Time
+const LOCAL_DATETIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M",
+];
 
 #[derive(Serialize, Debug, Clone)]
 pub struct Article {
     pub title: String,
+    /// Explicit url slug, overrides the one derived from the title
+    pub slug: Option<String>,
     pub author: String,
     pub date: DateTime<Tz>,
     pub content: String,
+    pub updated: Option<DateTime<Tz>>,
+    /// Drafts are skipped by the site build unless explicitly requested
+    pub draft: bool,
+    /// Scheduled publish time, falls back to `date` when not set
+    pub publish_at: Option<DateTime<Tz>>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub series_key: Option<String>,
+    pub series_pos: Option<u32>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub cover_image: Option<String>,
+    /// Whether the page shows a table of contents
+    pub toc: bool,
+    /// Frontmatter keys that aren't modelled above
+    pub extras: BTreeMap<String, serde_json::Value>,
+    /// Syntax the frontmatter is written in, kept when the article is saved
+    pub format: Format,
+    /// File the article was loaded from, named in rendering errors
+    #[serde(skip)]
+    pub source: Option<PathBuf>,
+    /// Line of the file the content starts on, 1-based
+    #[serde(skip)]
+    pub content_line: usize,
+}
+
+/// The content of an article as HTML
+#[derive(Serialize, Debug, Clone)]
+pub struct Rendered {
+    pub html: String,
+    /// Headings of the content, empty when the article opts out with `toc: false`
+    pub toc: Vec<render::TocEntry>,
+    /// Number of words in the content, code included
+    pub word_count: usize,
+    /// Plain text introducing the article, for listings and feeds
+    ///
+    /// The `summary` frontmatter when there is one, otherwise taken from the
+    /// content up to a `<!-- more -->` marker or from its opening paragraphs
+    pub excerpt: String,
+}
+
+/// Frontmatter form of a date, with the time to the second only when it isn't midnight
+fn frontmatter_date(date: DateTime<Tz>) -> String {
+    if date.time() == NaiveTime::MIN {
+        date.date_naive().to_string()
+    } else {
+        date.to_rfc3339_opts(SecondsFormat::Secs, true)
+    }
 }
 
 impl TryFrom<Article> for String {
@@ -29,15 +96,27 @@ impl TryFrom<Article> for String {
 
     fn try_from(value: Article) -> Result<Self, Self::Error> {
         let frontmatter = Frontmatter {
-            title: &value.title,
-            author: Some(&value.author),
-            date: &value.date.date_naive().to_string(),
+            title: Some(value.title),
+            slug: value.slug,
+            author: Some(value.author),
+            date: Some(frontmatter_date(value.date)),
+            updated: value.updated.map(frontmatter_date),
+            draft: value.draft,
+            publish_at: value.publish_at.map(frontmatter_date),
+            category: value.category,
+            tags: value.tags,
+            series_key: value.series_key,
+            series_pos: value.series_pos,
+            summary: value.summary,
+            description: value.description,
+            cover_image: value.cover_image,
+            toc: (!value.toc).then_some(false),
+            extras: value.extras,
         };
 
-        let frontmatter_yaml = serde_yaml::to_string(&frontmatter)
-            .map_err(|e| ArticleError::FrontMatterParse(e.to_string()))?;
+        let frontmatter = frontmatter.render(value.format)?;
 
-        Ok(format!("---\n{}---\n{}", frontmatter_yaml, value.content))
+        Ok(format!("{}{}", frontmatter, value.content))
     }
 }
 
@@ -45,35 +124,67 @@ impl TryFrom<String> for Article {
     type Error = ArticleError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        // Split the file into three parts delimited by `---`
-        // First part: empty string before the first delimiter
-        // Second part: the frontmatter
-        // Third part: the content
-        let parts: Vec<&str> = value.splitn(3, "---").collect();
-
-        // If the frontmatter is less than 3 parts we have an unexpected structure
-        if parts.len() < 3 {
-            return Err(ArticleError::FrontMatterParse(format!(
-                "Syntax error while reading article, too many parts"
-            )));
-        }
-
-        // Next, parse the content
-        let frontmatter_content = parts[1].trim();
-        let frontmatter: serde_yaml::Value = serde_yaml::from_str(frontmatter_content)
-            .map_err(|e| ArticleError::FrontMatterParse(e.to_string()))?;
+        // Split off the frontmatter block and parse it
+        let raw = RawArticle::split(&value)?;
+        let frontmatter = raw.parse()?;
 
         // Extract the metadata
-        let title = Self::extract_field("title", &frontmatter)?;
-        let author = Self::extract_field("author", &frontmatter).unwrap_or(DEFAULT_AUTHOR);
-        let date = Self::extract_date(&frontmatter)?;
+        let title = frontmatter.title.ok_or(ArticleError::FrontMatterParse(
+            "Failed to parse field: title".to_string(),
+        ))?;
+        let author = frontmatter
+            .author
+            .unwrap_or_else(|| DEFAULT_AUTHOR.to_string());
+        let date = frontmatter
+            .date
+            .as_deref()
+            .ok_or(ArticleError::FrontMatterParse(
+                "Failed to parse field: date".to_string(),
+            ))
+            .and_then(Self::parse_datetime)?;
+        let updated = frontmatter
+            .updated
+            .as_deref()
+            .map(Self::parse_datetime)
+            .transpose()?;
+        let publish_at = frontmatter
+            .publish_at
+            .as_deref()
+            .map(Self::parse_datetime)
+            .transpose()?;
+
+        // An explicit slug is used as-is, apart from stray separators
+        let slug = frontmatter
+            .slug
+            .map(|s| s.trim().trim_matches('/').to_string())
+            .filter(|s| !s.is_empty());
+
+        // The content runs to the end of the file, so its line follows from its length
+        let content = raw.content.trim_start();
+        let content_line = value[..value.len() - content.len()].matches('\n').count() + 1;
 
         // Create the article
         Ok(Article {
-            title: title.to_string(),
-            author: author.to_string(),
+            title,
+            slug,
+            author,
             date,
-            content: parts[2].trim().to_string(),
+            content: content.trim_end().to_string(),
+            updated,
+            draft: frontmatter.draft,
+            publish_at,
+            category: frontmatter.category,
+            tags: frontmatter.tags,
+            series_key: frontmatter.series_key,
+            series_pos: frontmatter.series_pos,
+            summary: frontmatter.summary,
+            description: frontmatter.description,
+            cover_image: frontmatter.cover_image,
+            extras: frontmatter.extras,
+            format: raw.format,
+            toc: frontmatter.toc.unwrap_or(true),
+            source: None,
+            content_line,
         })
     }
 }
@@ -84,98 +195,114 @@ impl Article {
         ArticleBuilder::default()
     }
 
-    /// Render the content of the article to HTML
-    pub fn render_html(&self) -> String {
-        let preprocessed_content = Self::preprocess_code_blocks(&self.content);
-
-        markdown::to_html(&preprocessed_content)
+    /// Render the content of the article to HTML, with highlighted and captioned code blocks
+    pub fn render_html(&self) -> Result<String, ArticleError> {
+        self.render(&render::Options::default(), &mut [])
+            .map(|rendered| rendered.html)
     }
 
-    /// Preprocesses the code blocks to extract additional metadata and
-    /// render the block itself correctly
-    fn preprocess_code_blocks(content: &str) -> String {
-        let mut result = String::new();
-        let mut in_code_block = false;
-        let mut pending_newline = false;
-
-        for line in content.lines() {
-            // Check if this line starts a code block
-            if line.trim_start().starts_with("```") {
-                let trimmed = line.trim_start();
-                let backticks_end = trimmed.chars().take_while(|&c| c == '`').count();
-                let after_backticks = &trimmed[backticks_end..];
-
-                if !in_code_block {
-                    // Opening code block
-                    in_code_block = true;
-                    result.push_str(line);
-                    result.push('\n');
-
-                    // If there's an info string (like "js vite.config.js"), add an extra newline
-                    // This fixes the indentation issue with the first line of code
-                    if !after_backticks.trim().is_empty() {
-                        pending_newline = true;
-                    }
-                } else {
-                    // Closing code block
-                    in_code_block = false;
-                    result.push_str(line);
-                    result.push('\n');
-                }
-            } else {
-                // Regular line
-                if pending_newline {
-                    // Add the extra newline after the opening ``` with info string
-                    result.push('\n');
-                    pending_newline = false;
-                }
-                result.push_str(line);
-                result.push('\n');
-            }
+    /// Render the content of the article, running the transforms over it first
+    ///
+    /// Headings get their anchors after every other transform has run, so
+    /// the table of contents matches the page, and the words are counted from
+    /// the same document before shortcodes are rendered last. Errors point at
+    /// the file the article was loaded from, with lines counted from the top
+    /// of the file
+    pub fn render(
+        &self,
+        options: &render::Options,
+        transforms: &mut [&mut dyn render::Transform],
+    ) -> Result<Rendered, ArticleError> {
+        let mut document = render::parse(&self.content, options).map_err(|e| self.locate(e))?;
+        for transform in transforms.iter_mut() {
+            render::transform::apply(*transform, &mut document);
         }
+        let mut anchors = render::Anchors::default();
+        render::transform::apply(&mut anchors, &mut document);
+        let text = reading::Text::read(&mut document);
+        render::shortcodes::expand(&mut document, options).map_err(|e| self.locate(e))?;
+
+        Ok(Rendered {
+            html: render::emit(&document),
+            toc: if self.toc { anchors.toc() } else { Vec::new() },
+            word_count: text.word_count(),
+            excerpt: match &self.summary {
+                Some(summary) => summary.trim().to_string(),
+                None => text.excerpt(),
+            },
+        })
+    }
 
-        // Remove the last newline if we added one
-        if result.ends_with('\n') {
-            result.pop();
+    /// Places an error from rendering the content in the article's file
+    fn locate(&self, error: ArticleError) -> ArticleError {
+        let error = match error {
+            ArticleError::Math { line, message } => ArticleError::Math {
+                line: line + self.content_line - 1,
+                message,
+            },
+            ArticleError::Link { line, target } => ArticleError::Link {
+                line: line + self.content_line - 1,
+                target,
+            },
+            error => error,
+        };
+        match &self.source {
+            Some(path) => error.in_file(path),
+            None => error,
         }
-
-        result
     }
 
-    /// Loads a list of articles from a directory
+    /// Loads a list of articles from a directory, newest first
     pub fn from_dir(dir: PathBuf) -> Result<Vec<Article>, ArticleError> {
         let mut articles = Vec::new();
-        let dir_entries = fs::read_dir(dir)?;
+        let dir_entries = fs::read_dir(&dir).map_err(ArticleError::io_at(&dir))?;
         for entry in dir_entries {
-            let entry = entry.map_err(ArticleError::IO)?;
+            let entry = entry.map_err(ArticleError::io_at(&dir))?;
             let path = entry.path();
 
             if path.is_file() && path.extension().and_then(|ext| ext.to_str()) == Some("md") {
-                let file_content = fs::read_to_string(&path)?;
-                let article = Article::try_from(file_content)?;
-
-                articles.push(article);
+                articles.push(Article::from_file(&path)?);
             }
         }
 
+        Self::sort(&mut articles);
         Ok(articles)
     }
 
+    /// Loads a single article from a file
+    pub fn from_file(path: &Path) -> Result<Article, ArticleError> {
+        let file_content = fs::read_to_string(path).map_err(ArticleError::io_at(path))?;
+        let article = Article::try_from(file_content).map_err(|e| e.in_file(path))?;
+        Ok(Article {
+            source: Some(path.to_path_buf()),
+            ..article
+        })
+    }
+
+    /// Sorts articles by date, newest first, falling back to the title for a stable order
+    pub fn sort(articles: &mut [Article]) {
+        articles.sort_by(|a, b| b.date.cmp(&a.date).then_with(|| a.title.cmp(&b.title)));
+    }
+
     /// Saves the article to a file
-    pub fn save(self, output_dir: &PathBuf) -> Result<(), ArticleError> {
+    ///
+    /// An article loaded from a file can be saved over it, keeping the format
+    /// of its frontmatter, any other existing file is left alone
+    pub fn save(self, output_dir: &Path) -> Result<(), ArticleError> {
         // Make sure the output directory is a directory and exists
         if !output_dir.exists() || !output_dir.is_dir() {
-            return Err(ArticleError::IO(std::io::Error::new(
+            return Err(ArticleError::io_at(output_dir)(std::io::Error::new(
                 std::io::ErrorKind::NotFound,
-                format!("Directory specified is invalid: {}", output_dir.display()),
+                "Directory specified is invalid",
             )));
         }
 
         // Construct the output path and validate it doesn't already exist
         let file_name = format!("{}.md", self.filename());
         let output_path = output_dir.join(file_name);
-        if output_path.exists() {
-            return Err(ArticleError::IO(std::io::Error::new(
+        let source = self.source.as_deref().and_then(|s| s.canonicalize().ok());
+        if output_path.exists() && source != output_path.canonicalize().ok() {
+            return Err(ArticleError::io_at(&output_path)(std::io::Error::new(
                 std::io::ErrorKind::AlreadyExists,
                 "Output file already exists",
             )));
@@ -185,29 +312,14 @@ impl Article {
         let content = String::try_from(self)?;
 
         // Write the file
-        std::fs::write(&output_path, content)?;
+        std::fs::write(&output_path, content).map_err(ArticleError::io_at(&output_path))?;
 
         tracing::debug!("Article written to {}", output_path.display());
         Ok(())
     }
 
-    /// Utility function for extracting a single field from frontmatter
-    pub fn extract_field<'a>(
-        field: &str,
-        frontmatter: &'a serde_yaml::Value,
-    ) -> Result<&'a str, ArticleError> {
-        frontmatter[field]
-            .as_str()
-            .ok_or(ArticleError::FrontMatterParse(format!(
-                "Failed to parse field: {field}"
-            )))
-    }
-
-    /// Utility function for extracting and parsing the DateTime
-    pub fn extract_date(frontmatter: &serde_yaml::Value) -> Result<DateTime<Tz>, ArticleError> {
-        // Get the date String
-        let date = Self::extract_field("date", frontmatter)?;
-
+    /// Utility function for parsing a frontmatter date (YYYY-MM-DD) into Pacific Time
+    pub fn parse_date(date: &str) -> Result<DateTime<Tz>, ArticleError> {
         // Parse to a Naive Date (YYYY-MM-DD format)
         let naive_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
             .map_err(|e| ArticleError::FrontMatterParse(e.to_string()))?;
@@ -228,14 +340,49 @@ impl Article {
         Ok(pt)
     }
 
-    /// Utility function for serializing the title into safe filename
-    pub fn serialize_title(&self) -> String {
-        self.title.to_lowercase().replace(' ', "-").replace(',', "")
+    /// Utility function for parsing a frontmatter timestamp
+    ///
+    /// Accepts RFC 3339 (`2024-10-21T09:00:00-07:00`), a Pacific Time
+    /// timestamp without an offset (`2024-10-21T09:00:00`, `2024-10-21 09:00`)
+    /// or a plain date
+    pub fn parse_datetime(datetime: &str) -> Result<DateTime<Tz>, ArticleError> {
+        if let Ok(dt) = DateTime::parse_from_rfc3339(datetime) {
+            return Ok(dt.with_timezone(&Pacific));
+        }
+
+        let local = LOCAL_DATETIME_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(datetime, format).ok());
+        if let Some(naive_datetime) = local {
+            return Pacific.from_local_datetime(&naive_datetime).single().ok_or(
+                ArticleError::FrontMatterParse("Ambiguous or invalid local time".to_string()),
+            );
+        }
+
+        Self::parse_date(datetime)
+    }
+
+    /// The moment the article becomes public, `publish_at` or its date
+    pub fn published_at(&self) -> DateTime<Tz> {
+        self.publish_at.unwrap_or(self.date)
+    }
+
+    /// Whether the article is scheduled for a time after `now`
+    pub fn is_scheduled(&self, now: DateTime<Utc>) -> bool {
+        self.published_at() > now
+    }
+
+    /// The url slug of the article, the `slug` frontmatter or one derived from the title
+    pub fn slug(&self) -> String {
+        match &self.slug {
+            Some(slug) => slug.clone(),
+            None => slugify(&self.title),
+        }
     }
 
-    /// Utility for serializing the file name {date}-{title}
+    /// Utility for serializing the file name {date}-{slug}
     pub fn filename(&self) -> String {
-        format!("{}", self.serialize_title())
+        format!("{}-{}", self.date.format("%Y-%m-%d"), self.slug())
     }
 
     /// Utility function for getting year
@@ -258,3 +405,92 @@ impl Article {
         &self.title
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Article;
+    use crate::frontmatter::Format;
+    use chrono::{NaiveTime, TimeZone};
+    use chrono_tz::US::Pacific;
+
+    /// Parses an article, saves it to text and parses that again
+    fn round_trip(source: &str) -> (Article, String) {
+        let article = Article::try_from(source.to_string()).unwrap();
+        let saved = String::try_from(article.clone()).unwrap();
+        let reparsed = Article::try_from(saved.clone()).unwrap();
+
+        assert_eq!(reparsed.format, article.format);
+        assert_eq!(reparsed.title, article.title);
+        assert_eq!(reparsed.date, article.date);
+        assert_eq!(reparsed.updated, article.updated);
+        assert_eq!(reparsed.publish_at, article.publish_at);
+        assert_eq!(reparsed.tags, article.tags);
+        assert_eq!(reparsed.extras, article.extras);
+        assert_eq!(reparsed.content, article.content);
+        (article, saved)
+    }
+
+    #[test]
+    fn round_trips_yaml() {
+        let (article, saved) =
+            round_trip("---\ntitle: Yaml\ndate: 2024-05-12\ntags: [a, b]\nweight: 3\n---\nBody\n");
+        assert_eq!(article.format, Format::Yaml);
+        assert!(saved.starts_with("---\n"));
+        assert!(saved.ends_with("---\nBody"));
+    }
+
+    #[test]
+    fn round_trips_toml() {
+        let (article, saved) = round_trip(
+            "+++\ntitle = \"Toml\"\ndate = 2024-05-12T10:00:00\n\
+             publish_at = 2024-05-13T08:30:00Z\nweight = 3\n+++\nBody\n",
+        );
+        assert_eq!(article.format, Format::Toml);
+        assert_eq!(
+            article.date,
+            Pacific.with_ymd_and_hms(2024, 5, 12, 10, 0, 0).unwrap()
+        );
+        assert!(saved.starts_with("+++\n"));
+        assert!(saved.contains("date = \"2024-05-12T10:00:00-07:00\"\n"));
+        assert!(saved.contains("publish_at = \"2024-05-13T01:30:00-07:00\"\n"));
+    }
+
+    #[test]
+    fn round_trips_json() {
+        let (article, saved) = round_trip(
+            "{\n  \"title\": \"Json\",\n  \"date\": \"2024-05-12T10:00:00\",\n  \
+             \"updated\": \"2024-06-01\"\n}\nBody\n",
+        );
+        assert_eq!(article.format, Format::Json);
+        assert!(saved.starts_with("{\n"));
+        assert!(saved.contains("\"updated\": \"2024-06-01\""));
+    }
+
+    #[test]
+    fn dates_new_articles_by_the_day() {
+        let article = Article::builder().title("New".to_string()).build();
+        assert_eq!(article.date.time(), NaiveTime::MIN);
+
+        let saved = String::try_from(article).unwrap();
+        let date = saved
+            .lines()
+            .find(|line| line.starts_with("date:"))
+            .unwrap();
+        assert_eq!(date.len(), "date: 2024-05-12".len(), "{date}");
+    }
+
+    #[test]
+    fn prefixes_file_names_with_the_date() {
+        let article = Article::try_from(
+            "---\ntitle: What's new? Rust 2024\ndate: 2024-05-02T23:30:00-07:00\n---\n".to_string(),
+        )
+        .unwrap();
+        assert_eq!(article.filename(), "2024-05-02-what-s-new-rust-2024");
+
+        let article = Article::builder()
+            .title("Ignored".to_string())
+            .maybe_slug(Some("My Slug!".to_string()))
+            .build();
+        assert!(article.filename().ends_with("-my-slug"));
+    }
+}
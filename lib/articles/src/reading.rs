@@ -0,0 +1,101 @@
+use crate::render::{Block, CodeBlock, Document, Heading, Transform, plain_text, transform};
+
+/// Reading speed reading times are estimated with
+pub const WORDS_PER_MINUTE: usize = 200;
+
+/// Length of excerpts taken from the start of an article
+pub const EXCERPT_WORDS: usize = 50;
+
+/// Line marking the end of the excerpt in an article's content
+pub const MORE_MARKER: &str = "<!-- more -->";
+
+/// Text of a document as readers see it
+#[derive(Default)]
+pub(crate) struct Text {
+    /// Paragraphs, which excerpts are taken from
+    paragraphs: Vec<String>,
+    /// Headings, table cells and code, only counted
+    other: Vec<String>,
+    /// Number of paragraphs before the `<!-- more -->` marker, if there is one
+    intro: Option<usize>,
+}
+
+impl Transform for Text {
+    fn block(&mut self, block: &mut Block) {
+        match block {
+            Block::Paragraph { content, .. } => {
+                let text = plain_text(content);
+                // shortcodes aren't rendered here, and aren't prose anyway
+                if !text.trim_start().starts_with("{{<") {
+                    self.paragraphs.push(text);
+                }
+            }
+            Block::HtmlBlock { content, .. } if content.trim() == MORE_MARKER => {
+                self.intro.get_or_insert(self.paragraphs.len());
+            }
+            Block::Table(table) => self
+                .other
+                .extend(table.rows.iter().flatten().map(|cell| plain_text(cell))),
+            _ => {}
+        }
+    }
+
+    fn heading(&mut self, heading: &mut Heading) {
+        self.other.push(plain_text(&heading.content));
+    }
+
+    fn code_block(&mut self, code: &mut CodeBlock) {
+        self.other.push(code.literal.clone());
+    }
+}
+
+impl Text {
+    /// Reads the text of a parsed document
+    pub fn read(document: &mut Document) -> Self {
+        let mut text = Text::default();
+        transform::apply(&mut text, document);
+        text
+    }
+
+    /// Number of words, including code
+    pub fn word_count(&self) -> usize {
+        self.paragraphs
+            .iter()
+            .chain(&self.other)
+            .map(|text| text.split_whitespace().count())
+            .sum()
+    }
+
+    /// Plain text introducing the document
+    ///
+    /// The paragraphs before a `<!-- more -->` line, or otherwise the opening
+    /// paragraphs cut off after [`EXCERPT_WORDS`] words
+    pub fn excerpt(&self) -> String {
+        if let Some(intro) = self.intro {
+            return words(&self.paragraphs[..intro]).join(" ");
+        }
+
+        let words = words(&self.paragraphs);
+        if words.len() <= EXCERPT_WORDS {
+            return words.join(" ");
+        }
+        let cut = words[..EXCERPT_WORDS].join(" ");
+        format!(
+            "{}…",
+            cut.trim_end_matches(|c: char| c.is_ascii_punctuation())
+        )
+    }
+}
+
+/// Minutes it takes to read a number of words, at least one
+pub fn reading_time_minutes(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE).max(1)
+}
+
+/// Every word of some paragraphs, in order
+fn words(paragraphs: &[String]) -> Vec<&str> {
+    paragraphs
+        .iter()
+        .flat_map(|p| p.split_whitespace())
+        .collect()
+}
@@ -0,0 +1,153 @@
+mod admonitions;
+mod commonmark;
+mod footnotes;
+mod gfm;
+mod html;
+mod links;
+mod math;
+mod parser;
+pub mod shortcodes;
+pub mod toc;
+pub mod transform;
+
+use crate::errors::ArticleError;
+pub use html::{emit, escape};
+pub(crate) use html::{escape_into, plain_text};
+pub use links::CrossLinks;
+use markdown_ppp::ast::{self, convert::WithData, generic};
+use parser::Parser;
+pub use shortcodes::{Shortcode, Shortcodes};
+use std::{borrow::Cow, sync::Arc};
+pub use toc::{Anchors, TocEntry};
+pub use transform::Transform;
+
+/// Markdown document with extra HTML attributes on its nodes
+pub type Document = generic::Document<Attributes>;
+pub type Block = generic::Block<Attributes>;
+pub type Inline = generic::Inline<Attributes>;
+pub type Heading = generic::Heading<Attributes>;
+pub type CodeBlock = generic::CodeBlock<Attributes>;
+pub type Link = generic::Link<Attributes>;
+pub type Image = generic::Image<Attributes>;
+
+/// Extra HTML attributes transforms can attach to any node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    /// Any other attributes, written in order
+    pub other: Vec<(String, String)>,
+    /// Whether a list's items are separated by blank lines, which wraps their text in paragraphs
+    pub loose: bool,
+}
+
+impl Attributes {
+    /// The attributes as they appear in a tag, with a leading space
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        if let Some(id) = &self.id {
+            html.push_str(&format!(" id=\"{}\"", html::escape(id)));
+        }
+        if !self.classes.is_empty() {
+            html.push_str(&format!(
+                " class=\"{}\"",
+                html::escape(&self.classes.join(" "))
+            ));
+        }
+        for (name, value) in &self.other {
+            html.push_str(&format!(" {name}=\"{}\"", html::escape(value)));
+        }
+        html
+    }
+}
+
+/// Which GitHub flavoured markdown extensions are understood, all of them by default
+#[derive(Clone)]
+pub struct Options {
+    pub tables: bool,
+    /// `~~struck~~` text
+    pub strikethrough: bool,
+    /// `- [x]` list items as checkboxes
+    pub task_lists: bool,
+    /// Bare urls, `www.` addresses and emails as links
+    pub autolinks: bool,
+    /// `[^note]` references with the notes collected at the end
+    pub footnotes: bool,
+    /// `$inline$` and `$$display$$` TeX math as MathML
+    pub math: bool,
+    /// Renders `{{< name >}}` shortcodes, which are left as text without it
+    pub shortcodes: Option<Arc<dyn Shortcodes>>,
+    /// Resolves `[[article]]` and `@article:` links, which are left as written without it
+    pub links: Option<Arc<CrossLinks>>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            autolinks: true,
+            footnotes: true,
+            math: true,
+            shortcodes: None,
+            links: None,
+        }
+    }
+}
+
+/// Parses markdown into a document
+///
+/// CommonMark plus the GitHub extensions enabled in the options, along with
+/// `> [!NOTE]` alerts, `:::note` containers, shortcodes, cross-links and math.
+/// Math that doesn't parse and links to unknown articles are errors naming
+/// their line in the markdown
+pub fn parse(markdown: &str, options: &Options) -> Result<Document, ArticleError> {
+    let markdown = if options.math {
+        Cow::Owned(math::mark(markdown)?)
+    } else {
+        Cow::Borrowed(markdown)
+    };
+    let mut document = prepare(Parser::new(options).parse(&markdown)?, options);
+    if let Some(links) = &options.links {
+        links.resolve(&mut document, &markdown)?;
+    }
+
+    Ok(document)
+}
+
+/// Adds attributes to a parsed document and handles the extensions the parser leaves to us
+fn prepare(document: ast::Document, options: &Options) -> Document {
+    let mut document = document.with_default_data();
+    transform::apply(&mut commonmark::LooseLists, &mut document);
+    transform::apply(&mut commonmark::EmptyLinks, &mut document);
+    if options.autolinks {
+        transform::apply(&mut gfm::Autolinks, &mut document);
+    }
+    if !options.task_lists {
+        transform::apply(&mut gfm::PlainTaskLists, &mut document);
+    }
+    if options.math {
+        transform::apply(&mut math::Math, &mut document);
+    }
+    if options.footnotes {
+        transform::apply(&mut footnotes::Footnotes, &mut document);
+    }
+
+    document
+}
+
+/// Renders markdown to HTML, running the transforms over the document in order first
+pub fn render(
+    markdown: &str,
+    options: &Options,
+    transforms: &mut [&mut dyn Transform],
+) -> Result<String, ArticleError> {
+    let mut document = parse(markdown, options)?;
+    for transform in transforms.iter_mut() {
+        transform::apply(*transform, &mut document);
+    }
+    shortcodes::expand(&mut document, options)?;
+
+    Ok(emit(&document))
+}
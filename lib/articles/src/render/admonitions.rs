@@ -0,0 +1,119 @@
+use super::parser::{Fence, Parser, mismatch, split_line};
+use markdown_ppp::ast::{Block, GitHubAlert, GitHubAlertType};
+use nom::IResult;
+
+/// Class and title of a callout kind, unknown kinds have none and render as
+/// plain block quotes
+pub(crate) fn kind(alert_type: &GitHubAlertType) -> Option<(&'static str, &'static str)> {
+    match alert_type {
+        GitHubAlertType::Note => Some(("note", "Note")),
+        GitHubAlertType::Tip => Some(("tip", "Tip")),
+        GitHubAlertType::Important => Some(("important", "Important")),
+        GitHubAlertType::Warning => Some(("warning", "Warning")),
+        GitHubAlertType::Caution => Some(("caution", "Caution")),
+        GitHubAlertType::Custom(_) => None,
+    }
+}
+
+/// Callout kind named after the colons of a container, with a few common aliases
+fn alert_type(name: &str) -> GitHubAlertType {
+    match name.to_ascii_lowercase().as_str() {
+        "note" | "info" => GitHubAlertType::Note,
+        "tip" | "hint" => GitHubAlertType::Tip,
+        "important" => GitHubAlertType::Important,
+        "warning" => GitHubAlertType::Warning,
+        "caution" | "danger" | "error" => GitHubAlertType::Caution,
+        _ => GitHubAlertType::Custom(name.to_string()),
+    }
+}
+
+/// Parses a container, from a line of three or more colons followed by a kind
+/// up to a line of at least as many colons, or the end of the document
+///
+/// ```markdown
+/// :::tip
+/// Containers can hold any markdown, *including* other containers
+/// :::
+/// ```
+///
+/// Read into the same node as `> [!TIP]` alerts
+pub(crate) fn container<'a>(input: &'a str, parser: &Parser) -> IResult<&'a str, Vec<Block>> {
+    // opening line, indented by at most three spaces
+    let (first, mut rest) = split_line(input);
+    let opening = first.trim_start_matches(' ');
+    if first.len() - opening.len() > 3 {
+        return mismatch(input);
+    }
+    let colons = opening.chars().take_while(|c| *c == ':').count();
+    let name = opening[colons..].trim();
+    if colons < 3
+        || name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return mismatch(input);
+    }
+
+    // body, keeping track of the containers nested inside it, colons in code
+    // fences are the code's
+    let mut body = Vec::new();
+    let mut depth = 0;
+    let mut fence: Option<Fence> = None;
+    while !rest.is_empty() {
+        let (line, next) = split_line(rest);
+        rest = next;
+        if let Some(open) = &fence {
+            if open.closes(line) {
+                fence = None;
+            }
+            body.push(line);
+            continue;
+        }
+        if let Some(open) = Fence::open(line) {
+            fence = Some(open);
+            body.push(line);
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let line_colons = trimmed.chars().take_while(|c| *c == ':').count();
+        if line_colons >= 3 && trimmed[line_colons..].trim().is_empty() {
+            if depth > 0 {
+                depth -= 1;
+            } else if line_colons >= colons {
+                break;
+            }
+        } else if line_colons >= 3 {
+            depth += 1;
+        }
+        body.push(line);
+    }
+
+    let document = match parser.parse(&body.join("\n")) {
+        Ok(document) => document,
+        Err(error) => return parser.fail(input, error),
+    };
+
+    Ok((
+        rest,
+        vec![Block::GitHubAlert(GitHubAlert {
+            alert_type: alert_type(name),
+            blocks: document.blocks,
+        })],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{Options, render};
+
+    #[test]
+    fn leaves_colons_in_code_fences_alone() {
+        let markdown = "::::note\nWrite\n\n```markdown\n:::tip\nHi\n:::\n```\n\n~~~\n::::\n~~~\n::::\n\nAfter\n";
+        let html = render(markdown, &Options::default(), &mut []).unwrap();
+        assert!(html.contains(":::tip\nHi\n:::\n"), "{html}");
+        assert!(html.contains("::::\n</code>"), "{html}");
+        assert!(html.ends_with("</aside>\n<p>After</p>"), "{html}");
+    }
+}
@@ -0,0 +1,153 @@
+use super::{Block, Document, Inline, Link, Transform};
+use markdown_ppp::ast::generic::{GitHubAlertNode, ListKind};
+
+/// Joins the lists the parser splits a loose list into
+///
+/// Items separated by blank lines come back as adjacent one-item lists of the
+/// same kind. The joined list is marked loose, so its items keep their paragraphs
+pub(crate) struct LooseLists;
+
+impl Transform for LooseLists {
+    fn block(&mut self, block: &mut Block) {
+        match block {
+            Block::BlockQuote { blocks, .. }
+            | Block::GitHubAlert(GitHubAlertNode { blocks, .. }) => join(blocks),
+            Block::List(list) => list
+                .items
+                .iter_mut()
+                .for_each(|item| join(&mut item.blocks)),
+            Block::FootnoteDefinition(footnote) => join(&mut footnote.blocks),
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self, document: &mut Document) {
+        join(&mut document.blocks);
+    }
+}
+
+/// Links `[text]()`, which the parser reads as a reference followed by `()` text
+pub(crate) struct EmptyLinks;
+
+impl Transform for EmptyLinks {
+    fn block(&mut self, block: &mut Block) {
+        match block {
+            Block::Paragraph { content, .. } => link_empty(content),
+            Block::Heading(heading) => link_empty(&mut heading.content),
+            Block::Table(table) => table.rows.iter_mut().flatten().for_each(link_empty),
+            _ => {}
+        }
+    }
+}
+
+/// Joins runs of adjacent lists of the same kind into one loose list
+fn join(blocks: &mut Vec<Block>) {
+    let mut joined: Vec<Block> = Vec::with_capacity(blocks.len());
+    for block in blocks.drain(..) {
+        match (joined.last_mut(), block) {
+            (Some(Block::List(previous)), Block::List(list))
+                if same_kind(&previous.kind, &list.kind) =>
+            {
+                previous.items.extend(list.items);
+                previous.user_data.loose = true;
+            }
+            (_, block) => joined.push(block),
+        }
+    }
+    *blocks = joined;
+}
+
+/// Whether two lists could be items of the same list, ordered lists keep the first start
+fn same_kind(a: &ListKind, b: &ListKind) -> bool {
+    match (a, b) {
+        (ListKind::Bullet(a), ListKind::Bullet(b)) => a == b,
+        (ListKind::Ordered(_), ListKind::Ordered(_)) => true,
+        _ => false,
+    }
+}
+
+/// Turns references directly followed by `()` into links without a destination,
+/// inside formatting too
+fn link_empty(inlines: &mut Vec<Inline>) {
+    let mut i = 0;
+    while i < inlines.len() {
+        if let Inline::Emphasis { content, .. }
+        | Inline::Strong { content, .. }
+        | Inline::Strikethrough { content, .. } = &mut inlines[i]
+        {
+            link_empty(content);
+        }
+
+        let empty = matches!(inlines[i], Inline::LinkReference(_))
+            && matches!(
+                inlines.get(i + 1),
+                Some(Inline::Text { content, .. }) if content.starts_with("()")
+            );
+        if empty {
+            if let Some(Inline::Text { content, .. }) = inlines.get_mut(i + 1) {
+                content.drain(..2);
+                if content.is_empty() {
+                    inlines.remove(i + 1);
+                }
+            }
+            if let Inline::LinkReference(reference) = inlines.remove(i) {
+                inlines.insert(
+                    i,
+                    Inline::Link(Link {
+                        destination: String::new(),
+                        title: None,
+                        children: reference.text,
+                        user_data: reference.user_data,
+                    }),
+                );
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{Options, render};
+
+    fn html(markdown: &str) -> String {
+        render(markdown, &Options::default(), &mut []).unwrap()
+    }
+
+    #[test]
+    fn joins_loose_bullet_lists() {
+        assert_eq!(
+            html("- a\n\n- b\n"),
+            "<ul>\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ul>"
+        );
+    }
+
+    #[test]
+    fn joins_loose_ordered_lists() {
+        assert_eq!(
+            html("3. a\n\n4. b\n"),
+            "<ol start=\"3\">\n<li>\n<p>a</p>\n</li>\n<li>\n<p>b</p>\n</li>\n</ol>"
+        );
+    }
+
+    #[test]
+    fn keeps_lists_with_other_markers_apart() {
+        assert_eq!(
+            html("- a\n\n* b\n"),
+            "<ul>\n<li>a</li>\n</ul>\n<ul>\n<li>b</li>\n</ul>"
+        );
+    }
+
+    #[test]
+    fn keeps_tight_lists_tight() {
+        assert_eq!(html("- a\n- b\n"), "<ul>\n<li>a</li>\n<li>b</li>\n</ul>");
+    }
+
+    #[test]
+    fn links_empty_destinations() {
+        assert_eq!(
+            html("Use [a]() and *[b]()*."),
+            "<p>Use <a href=\"\">a</a> and <em><a href=\"\">b</a></em>.</p>"
+        );
+    }
+}
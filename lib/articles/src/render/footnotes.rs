@@ -0,0 +1,161 @@
+use super::{Attributes, Block, Document, Inline, Transform, html::escape, transform};
+use markdown_ppp::ast::{
+    ListOrderedKindOptions,
+    generic::{FootnoteDefinition, List, ListItem, ListKind},
+};
+use std::collections::HashMap;
+
+/// Numbers footnote references and gathers the notes into a section at the end
+///
+/// Notes are numbered in the order they're first referenced, each links back
+/// to every place it's referenced from. Notes nobody references are dropped,
+/// references to notes that don't exist stay as written
+pub(crate) struct Footnotes;
+
+impl Transform for Footnotes {
+    fn finish(&mut self, document: &mut Document) {
+        // take the notes out of the document
+        let (definitions, blocks): (Vec<_>, Vec<_>) = std::mem::take(&mut document.blocks)
+            .into_iter()
+            .partition(|block| matches!(block, Block::FootnoteDefinition(_)));
+        document.blocks = blocks;
+        let mut notes = HashMap::new();
+        for block in definitions {
+            if let Block::FootnoteDefinition(note) = block {
+                notes.entry(note.label.clone()).or_insert(note);
+            }
+        }
+
+        // number the references
+        let mut references = References {
+            notes: &notes,
+            order: Vec::new(),
+            counts: HashMap::new(),
+        };
+        transform::apply(&mut references, document);
+        let References { order, counts, .. } = references;
+        if order.is_empty() {
+            return;
+        }
+
+        let items = order
+            .into_iter()
+            .filter_map(|label| {
+                let note = notes.remove(&label)?;
+                let count = counts.get(&label).copied().unwrap_or(1);
+                Some(item(note, count))
+            })
+            .collect();
+
+        document
+            .blocks
+            .push(html("<section class=\"footnotes\" role=\"doc-endnotes\">"));
+        document.blocks.push(Block::List(List {
+            kind: ListKind::Ordered(ListOrderedKindOptions { start: 1 }),
+            items,
+            user_data: Default::default(),
+        }));
+        document.blocks.push(html("</section>"));
+    }
+}
+
+/// Replaces footnote references with numbered links to their notes
+struct References<'a> {
+    notes: &'a HashMap<String, FootnoteDefinition<Attributes>>,
+    /// Labels in the order they're first referenced
+    order: Vec<String>,
+    /// How often each label is referenced
+    counts: HashMap<String, usize>,
+}
+
+impl Transform for References<'_> {
+    fn inline(&mut self, inline: &mut Inline) {
+        let Inline::FootnoteReference { label, .. } = inline else {
+            return;
+        };
+        if !self.notes.contains_key(label.as_str()) {
+            *inline = Inline::Text {
+                content: format!("[^{label}]"),
+                user_data: Default::default(),
+            };
+            return;
+        }
+
+        let count = self.counts.entry(label.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            self.order.push(label.clone());
+        }
+        let number = self.order.iter().position(|l| l == label).unwrap_or(0) + 1;
+        let id = key(label);
+        let reference_id = reference_id(&id, *count);
+
+        *inline = Inline::Html {
+            content: format!(
+                "<sup class=\"footnote-ref\"><a href=\"#fn-{id}\" id=\"{reference_id}\" role=\"doc-noteref\">{number}</a></sup>"
+            ),
+            user_data: Default::default(),
+        };
+    }
+}
+
+/// A note in the footnotes list, ending in links back to its references
+fn item(note: FootnoteDefinition<Attributes>, count: usize) -> ListItem<Attributes> {
+    let id = key(&note.label);
+    let backlinks = (1..=count)
+        .map(|n| {
+            let marker = if n == 1 {
+                String::new()
+            } else {
+                format!("<sup>{n}</sup>")
+            };
+            format!(
+                " <a href=\"#{}\" class=\"footnote-backref\" role=\"doc-backlink\">&#8617;{marker}</a>",
+                reference_id(&id, n)
+            )
+        })
+        .collect::<String>();
+    let backlinks = Inline::Html {
+        content: backlinks,
+        user_data: Default::default(),
+    };
+
+    // the links go at the end of the last paragraph, or in one of their own
+    let mut blocks = note.blocks;
+    match blocks.last_mut() {
+        Some(Block::Paragraph { content, .. }) => content.push(backlinks),
+        _ => blocks.push(Block::Paragraph {
+            content: vec![backlinks],
+            user_data: Default::default(),
+        }),
+    }
+
+    ListItem {
+        task: None,
+        blocks,
+        user_data: Attributes {
+            id: Some(format!("fn-{id}")),
+            ..Default::default()
+        },
+    }
+}
+
+/// Label of a note as used in ids
+fn key(label: &str) -> String {
+    escape(&label.split_whitespace().collect::<Vec<_>>().join("-"))
+}
+
+/// Id of the nth reference to a note
+fn reference_id(key: &str, n: usize) -> String {
+    match n {
+        1 => format!("fnref-{key}"),
+        n => format!("fnref-{key}-{n}"),
+    }
+}
+
+fn html(content: &str) -> Block {
+    Block::HtmlBlock {
+        content: content.to_string(),
+        user_data: Default::default(),
+    }
+}
@@ -0,0 +1,178 @@
+use super::{Block, Inline, Link, Transform};
+use markdown_ppp::ast::TaskState;
+
+/// Links bare urls, `www.` addresses and emails in text, as GitHub does
+///
+/// The parser only understands `<https://...>` autolinks
+pub(crate) struct Autolinks;
+
+impl Transform for Autolinks {
+    fn block(&mut self, block: &mut Block) {
+        match block {
+            Block::Paragraph { content, .. } => link_all(content),
+            Block::Heading(heading) => link_all(&mut heading.content),
+            Block::Table(table) => table.rows.iter_mut().flatten().for_each(link_all),
+            _ => {}
+        }
+    }
+}
+
+/// Writes task list boxes back as the text they came from
+pub(crate) struct PlainTaskLists;
+
+impl Transform for PlainTaskLists {
+    fn block(&mut self, block: &mut Block) {
+        let Block::List(list) = block else {
+            return;
+        };
+        for item in &mut list.items {
+            let Some(task) = item.task.take() else {
+                continue;
+            };
+            let marker = match task {
+                TaskState::Complete => "[x] ",
+                TaskState::Incomplete => "[ ] ",
+            };
+            let text = Inline::Text {
+                content: marker.to_string(),
+                user_data: Default::default(),
+            };
+            match item.blocks.first_mut() {
+                Some(Block::Paragraph { content, .. }) => content.insert(0, text),
+                _ => item.blocks.insert(
+                    0,
+                    Block::Paragraph {
+                        content: vec![text],
+                        user_data: Default::default(),
+                    },
+                ),
+            }
+        }
+    }
+}
+
+/// Links the urls in a list of inlines and inside their formatting, never inside
+/// links or code
+fn link_all(inlines: &mut Vec<Inline>) {
+    let mut linked = Vec::with_capacity(inlines.len());
+    let mut text = String::new();
+
+    for mut inline in inlines.drain(..) {
+        // the parser can split text anywhere, so runs are joined before looking
+        if let Inline::Text { content, .. } = &inline {
+            text.push_str(content);
+            continue;
+        }
+        linked.extend(split_links(&std::mem::take(&mut text)));
+        if let Inline::Emphasis { content, .. }
+        | Inline::Strong { content, .. }
+        | Inline::Strikethrough { content, .. } = &mut inline
+        {
+            link_all(content);
+        }
+        linked.push(inline);
+    }
+    linked.extend(split_links(&text));
+
+    *inlines = linked;
+}
+
+/// Splits text into plain text and links
+fn split_links(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut plain_start = 0;
+    let mut i = 0;
+
+    while let Some(c) = text[i..].chars().next() {
+        // links only start at the beginning of a word
+        let at_boundary = text[..i]
+            .chars()
+            .next_back()
+            .is_none_or(|p| p.is_whitespace() || "*_~(".contains(p));
+        if at_boundary && let Some((len, destination)) = link_at(&text[i..]) {
+            if plain_start < i {
+                inlines.push(plain(&text[plain_start..i]));
+            }
+            inlines.push(Inline::Link(Link {
+                destination,
+                title: None,
+                children: vec![plain(&text[i..i + len])],
+                user_data: Default::default(),
+            }));
+            i += len;
+            plain_start = i;
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    if plain_start < text.len() {
+        inlines.push(plain(&text[plain_start..]));
+    }
+
+    inlines
+}
+
+/// Length and destination of a url, `www.` address or email at the start of the text
+fn link_at(text: &str) -> Option<(usize, String)> {
+    let web_prefix = ["https://", "http://", "www."]
+        .into_iter()
+        .find(|prefix| text.starts_with(prefix));
+
+    if let Some(prefix) = web_prefix {
+        let len = url_len(text);
+        if len <= prefix.len() {
+            return None;
+        }
+        let url = &text[..len];
+        let destination = match prefix {
+            "www." => format!("http://{url}"),
+            _ => url.to_string(),
+        };
+        return Some((len, destination));
+    }
+
+    // name@domain.tld, the domain needs a dot and can't end in punctuation
+    let local = text
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "._+-".contains(c)))
+        .unwrap_or(text.len());
+    if local == 0 || !text[local..].starts_with('@') {
+        return None;
+    }
+    let domain = &text[local + 1..];
+    let domain_len = domain
+        .find(|c: char| !(c.is_ascii_alphanumeric() || ".-".contains(c)))
+        .unwrap_or(domain.len());
+    let domain = domain[..domain_len].trim_end_matches(['.', '-']);
+    if !domain.contains('.') {
+        return None;
+    }
+    let len = local + 1 + domain.len();
+    Some((len, format!("mailto:{}", &text[..len])))
+}
+
+/// Length of a url, without trailing punctuation or unbalanced closing parens
+fn url_len(text: &str) -> usize {
+    let end = text
+        .find(|c: char| c.is_whitespace() || c == '<')
+        .unwrap_or(text.len());
+    let mut url = &text[..end];
+
+    loop {
+        let trimmed = url.trim_end_matches(['?', '!', '.', ',', ':', '*', '_', '~', '\'', '"']);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(inner) if trimmed.matches(')').count() > trimmed.matches('(').count() => inner,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            return url.len();
+        }
+        url = trimmed;
+    }
+}
+
+fn plain(text: &str) -> Inline {
+    Inline::Text {
+        content: text.to_string(),
+        user_data: Default::default(),
+    }
+}
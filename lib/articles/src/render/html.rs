@@ -0,0 +1,379 @@
+use super::{Attributes, Block, Document, Inline, admonitions, toc};
+use crate::code::{self, CodeInfo};
+use markdown_ppp::ast::{
+    Alignment, CodeBlockKind, TaskState,
+    generic::{LinkDefinition, ListKind},
+};
+use std::collections::HashMap;
+
+/// Turns a document into HTML
+pub fn emit(document: &Document) -> String {
+    let mut emitter = Emitter {
+        definitions: HashMap::new(),
+        html: String::new(),
+    };
+    emitter.collect_definitions(&document.blocks);
+    emitter.blocks(&document.blocks, false);
+    emitter
+        .html
+        .truncate(emitter.html.trim_end_matches('\n').len());
+    emitter.html
+}
+
+/// Escapes text for HTML content and attributes
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    escape_into(&mut escaped, text);
+    escaped
+}
+
+/// Escapes text for HTML content and attributes onto the end of a string
+pub(crate) fn escape_into(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// The plain text of some inlines, as used for alt text and reference labels
+pub(crate) fn plain_text(inlines: &[Inline]) -> String {
+    let mut text = String::new();
+    for inline in inlines {
+        match inline {
+            Inline::Text { content, .. } | Inline::Code { content, .. } => text.push_str(content),
+            Inline::LineBreak { .. } => text.push(' '),
+            Inline::Link(link) => text.push_str(&plain_text(&link.children)),
+            Inline::Image(image) => text.push_str(&image.alt),
+            Inline::LinkReference(reference) => text.push_str(&plain_text(&reference.text)),
+            Inline::Emphasis { content, .. }
+            | Inline::Strong { content, .. }
+            | Inline::Strikethrough { content, .. } => text.push_str(&plain_text(content)),
+            Inline::Autolink { url, .. } => text.push_str(url),
+            Inline::Html { .. } | Inline::FootnoteReference { .. } | Inline::Empty { .. } => {}
+        }
+    }
+    text
+}
+
+/// Normalizes a link reference label for matching, case and whitespace don't count
+fn label_key(label: &[Inline]) -> String {
+    plain_text(label)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+struct Emitter<'a> {
+    /// Link reference definitions by label
+    definitions: HashMap<String, &'a LinkDefinition<Attributes>>,
+    html: String,
+}
+
+impl<'a> Emitter<'a> {
+    /// Finds the link reference definitions anywhere in the document, first one wins
+    fn collect_definitions(&mut self, blocks: &'a [Block]) {
+        for block in blocks {
+            match block {
+                Block::Definition(definition) => {
+                    self.definitions
+                        .entry(label_key(&definition.label))
+                        .or_insert(definition);
+                }
+                Block::BlockQuote { blocks, .. } => self.collect_definitions(blocks),
+                Block::List(list) => {
+                    for item in &list.items {
+                        self.collect_definitions(&item.blocks);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Writes blocks, a tight list item leaves its paragraphs unwrapped
+    fn blocks(&mut self, blocks: &[Block], tight: bool) {
+        for block in blocks {
+            self.block(block, tight);
+        }
+    }
+
+    fn block(&mut self, block: &Block, tight: bool) {
+        match block {
+            Block::Paragraph { content, user_data } => {
+                if tight && *user_data == Attributes::default() {
+                    self.inlines(content);
+                } else {
+                    self.open("p", user_data);
+                    self.inlines(content);
+                    self.html.push_str("</p>\n");
+                }
+            }
+            Block::Heading(heading) => {
+                let level = toc::level(heading);
+                self.open(&format!("h{level}"), &heading.user_data);
+                self.inlines(&heading.content);
+                self.html.push_str(&format!("</h{level}>\n"));
+            }
+            Block::ThematicBreak { user_data } => {
+                self.html
+                    .push_str(&format!("<hr{} />\n", user_data.to_html()));
+            }
+            Block::BlockQuote { blocks, user_data } => {
+                self.open("blockquote", user_data);
+                self.html.push('\n');
+                self.blocks(blocks, false);
+                self.html.push_str("</blockquote>\n");
+            }
+            Block::List(list) => {
+                let tag = match &list.kind {
+                    ListKind::Ordered(options) if options.start != 1 => {
+                        self.html.push_str(&format!(
+                            "<ol start=\"{}\"{}>\n",
+                            options.start,
+                            list.user_data.to_html()
+                        ));
+                        "ol"
+                    }
+                    ListKind::Ordered(_) => {
+                        self.open("ol", &list.user_data);
+                        self.html.push('\n');
+                        "ol"
+                    }
+                    ListKind::Bullet(_) => {
+                        self.open("ul", &list.user_data);
+                        self.html.push('\n');
+                        "ul"
+                    }
+                };
+
+                // A list is tight when its items aren't separated by blank lines
+                // and none has more than a single paragraph
+                let tight = !list.user_data.loose
+                    && list.items.iter().all(|item| {
+                        item.blocks
+                            .iter()
+                            .filter(|b| matches!(b, Block::Paragraph { .. }))
+                            .count()
+                            <= 1
+                    });
+                for item in &list.items {
+                    self.open("li", &item.user_data);
+                    if let Some(task) = item.task {
+                        let checked = match task {
+                            TaskState::Complete => " checked=\"\"",
+                            TaskState::Incomplete => "",
+                        };
+                        self.html.push_str(&format!(
+                            "<input type=\"checkbox\" disabled=\"\"{checked} /> "
+                        ));
+                    }
+                    for block in &item.blocks {
+                        // only the bare text of a tight item shares the line of its tags
+                        let inline = tight && matches!(block, Block::Paragraph { .. });
+                        if !inline && !self.html.ends_with('\n') {
+                            self.html.push('\n');
+                        }
+                        self.block(block, tight);
+                    }
+                    if !tight && !self.html.ends_with('\n') {
+                        self.html.push('\n');
+                    }
+                    self.html.push_str("</li>\n");
+                }
+                self.html.push_str(&format!("</{tag}>\n"));
+            }
+            Block::CodeBlock(code) => {
+                let info = match &code.kind {
+                    CodeBlockKind::Fenced { info } => {
+                        CodeInfo::parse(info.as_deref().unwrap_or_default())
+                    }
+                    CodeBlockKind::Indented => CodeInfo::parse(""),
+                };
+                self.html
+                    .push_str(&code::render_block(&info, &code.literal, &code.user_data));
+                self.html.push('\n');
+            }
+            Block::HtmlBlock { content, .. } => {
+                self.html.push_str(content);
+                if !content.ends_with('\n') {
+                    self.html.push('\n');
+                }
+            }
+            Block::Definition(_) | Block::Empty { .. } => {}
+            Block::Table(table) => {
+                self.open("table", &table.user_data);
+                self.html.push('\n');
+                for (i, row) in table.rows.iter().enumerate() {
+                    let cell_tag = if i == 0 { "th" } else { "td" };
+                    if i == 0 {
+                        self.html.push_str("<thead>\n");
+                    } else if i == 1 {
+                        self.html.push_str("<tbody>\n");
+                    }
+                    self.html.push_str("<tr>\n");
+                    for (column, cell) in row.iter().enumerate() {
+                        let align = match table.alignments.get(column) {
+                            Some(Alignment::Left) => " align=\"left\"",
+                            Some(Alignment::Center) => " align=\"center\"",
+                            Some(Alignment::Right) => " align=\"right\"",
+                            _ => "",
+                        };
+                        self.html.push_str(&format!("<{cell_tag}{align}>"));
+                        self.inlines(cell);
+                        self.html.push_str(&format!("</{cell_tag}>\n"));
+                    }
+                    self.html.push_str("</tr>\n");
+                    if i == 0 {
+                        self.html.push_str("</thead>\n");
+                    }
+                }
+                if table.rows.len() > 1 {
+                    self.html.push_str("</tbody>\n");
+                }
+                self.html.push_str("</table>\n");
+            }
+            Block::FootnoteDefinition(footnote) => {
+                let mut attributes = footnote.user_data.clone();
+                attributes
+                    .id
+                    .get_or_insert_with(|| format!("fn-{}", footnote.label));
+                attributes.classes.push("footnote".to_string());
+                self.open("div", &attributes);
+                self.html.push('\n');
+                self.blocks(&footnote.blocks, false);
+                self.html.push_str("</div>\n");
+            }
+            Block::GitHubAlert(alert) => match admonitions::kind(&alert.alert_type) {
+                Some((class, title)) => {
+                    let mut attributes = alert.user_data.clone();
+                    attributes.classes.splice(
+                        0..0,
+                        ["admonition".to_string(), format!("admonition-{class}")],
+                    );
+                    attributes
+                        .other
+                        .push(("role".to_string(), "note".to_string()));
+                    self.open("aside", &attributes);
+                    self.html
+                        .push_str(&format!("\n<p class=\"admonition-title\">{title}</p>\n"));
+                    self.blocks(&alert.blocks, false);
+                    self.html.push_str("</aside>\n");
+                }
+                None => {
+                    self.open("blockquote", &alert.user_data);
+                    self.html.push('\n');
+                    self.blocks(&alert.blocks, false);
+                    self.html.push_str("</blockquote>\n");
+                }
+            },
+        }
+    }
+
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            self.inline(inline);
+        }
+    }
+
+    fn inline(&mut self, inline: &Inline) {
+        match inline {
+            Inline::Text { content, .. } => self.html.push_str(&escape(content)),
+            Inline::LineBreak { .. } => self.html.push_str("<br />\n"),
+            Inline::Code { content, user_data } => {
+                self.open("code", user_data);
+                self.html.push_str(&escape(content));
+                self.html.push_str("</code>");
+            }
+            Inline::Html { content, .. } => self.html.push_str(content),
+            Inline::Link(link) => {
+                self.link(&link.destination, link.title.as_deref(), &link.user_data);
+                self.inlines(&link.children);
+                self.html.push_str("</a>");
+            }
+            Inline::LinkReference(reference) => {
+                match self.definitions.get(&label_key(&reference.label)) {
+                    Some(definition) => {
+                        self.link(
+                            &definition.destination,
+                            definition.title.as_deref(),
+                            &reference.user_data,
+                        );
+                        self.inlines(&reference.text);
+                        self.html.push_str("</a>");
+                    }
+                    // unresolved references stay the text they were written as
+                    None => {
+                        self.html.push('[');
+                        self.inlines(&reference.text);
+                        self.html.push(']');
+                    }
+                }
+            }
+            Inline::Image(image) => {
+                self.html.push_str(&format!(
+                    "<img src=\"{}\" alt=\"{}\"",
+                    escape(&image.destination),
+                    escape(&image.alt)
+                ));
+                if let Some(title) = &image.title {
+                    self.html.push_str(&format!(" title=\"{}\"", escape(title)));
+                }
+                self.html.push_str(&image.user_data.to_html());
+                self.html.push_str(" />");
+            }
+            Inline::Emphasis { content, user_data } => {
+                self.open("em", user_data);
+                self.inlines(content);
+                self.html.push_str("</em>");
+            }
+            Inline::Strong { content, user_data } => {
+                self.open("strong", user_data);
+                self.inlines(content);
+                self.html.push_str("</strong>");
+            }
+            Inline::Strikethrough { content, user_data } => {
+                self.open("del", user_data);
+                self.inlines(content);
+                self.html.push_str("</del>");
+            }
+            Inline::Autolink { url, user_data } => {
+                let href = if url.contains('@') && !url.contains(':') {
+                    format!("mailto:{url}")
+                } else {
+                    url.clone()
+                };
+                self.link(&href, None, user_data);
+                self.html.push_str(&escape(url));
+                self.html.push_str("</a>");
+            }
+            Inline::FootnoteReference { label, user_data } => {
+                self.open("sup", user_data);
+                self.html
+                    .push_str(&format!("<a href=\"#fn-{0}\">{0}</a></sup>", escape(label)));
+            }
+            Inline::Empty { .. } => {}
+        }
+    }
+
+    /// Writes an opening tag with the node's attributes
+    fn open(&mut self, tag: &str, attributes: &Attributes) {
+        self.html
+            .push_str(&format!("<{tag}{}>", attributes.to_html()));
+    }
+
+    /// Writes the opening tag of a link
+    fn link(&mut self, href: &str, title: Option<&str>, attributes: &Attributes) {
+        self.html.push_str(&format!("<a href=\"{}\"", escape(href)));
+        if let Some(title) = title {
+            self.html.push_str(&format!(" title=\"{}\"", escape(title)));
+        }
+        self.html.push_str(&attributes.to_html());
+        self.html.push('>');
+    }
+}
@@ -0,0 +1,236 @@
+use super::{Block, Document, Inline, Link, Transform, plain_text, transform};
+use crate::{Article, errors::ArticleError, slug::slugify};
+use std::collections::HashMap;
+
+/// Prefix of link destinations naming an article
+const ARTICLE_SCHEME: &str = "@article:";
+
+/// Where a cross-link lands
+#[derive(Debug, Clone)]
+struct Target {
+    url: String,
+    title: String,
+}
+
+/// Articles that can be linked to by name rather than url
+///
+/// ```markdown
+/// Picking up from [[2022-11-26-setting-up-my-own-static-site-generator]],
+/// see [[setting-up-my-own-static-site-generator-part-2|part two]] or
+/// [the styling](@article:2022-12-27-setting-up-my-own-static-site-generator-part-2.md#scss)
+/// ```
+///
+/// Articles are named by their file, with or without its date, their slug or
+/// their title. Wiki links without a label show the title of the article
+#[derive(Debug, Clone, Default)]
+pub struct CrossLinks {
+    targets: HashMap<String, Target>,
+}
+
+impl CrossLinks {
+    /// Indexes the articles, with `url` giving the address of each one's page
+    pub fn new<'a>(
+        articles: impl IntoIterator<Item = &'a Article>,
+        url: impl Fn(&Article) -> String,
+    ) -> Self {
+        let mut targets = HashMap::new();
+        for article in articles {
+            let target = Target {
+                url: url(article),
+                title: article.title.clone(),
+            };
+            let stem = article
+                .source
+                .as_ref()
+                .and_then(|path| path.file_stem())
+                .map(|stem| stem.to_string_lossy().into_owned());
+            let undated = stem.as_deref().and_then(undated).map(str::to_string);
+            let names = [
+                stem,
+                undated,
+                Some(article.filename()),
+                Some(article.slug()),
+                Some(slugify(&article.title)),
+            ];
+
+            // the first article to claim a name keeps it
+            for name in names.into_iter().flatten() {
+                targets.entry(name).or_insert_with(|| target.clone());
+            }
+        }
+
+        CrossLinks { targets }
+    }
+
+    /// Finds the article a link names, as written or slugified
+    fn find(&self, name: &str) -> Option<&Target> {
+        let name = name.trim();
+        let name = name.rsplit('/').next().unwrap_or(name);
+        let name = name.strip_suffix(".md").unwrap_or(name);
+        self.targets
+            .get(name)
+            .or_else(|| self.targets.get(&slugify(name)))
+    }
+
+    /// Points the cross-links of a parsed document at the articles they name
+    ///
+    /// Code and raw HTML aren't markdown, so they're never read as links.
+    /// Links that name no known article are an error at the first line of
+    /// the markdown they appear on
+    pub(crate) fn resolve(
+        &self,
+        document: &mut Document,
+        markdown: &str,
+    ) -> Result<(), ArticleError> {
+        let mut resolver = Resolver {
+            links: self,
+            unknown: None,
+        };
+        transform::apply(&mut resolver, document);
+
+        match resolver.unknown {
+            Some(target) => Err(ArticleError::Link {
+                line: line_of(markdown, &target),
+                target,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// The article a link names and the url it resolves to, keeping any `#fragment`
+    ///
+    /// Gives back the name without its fragment when there's no such article
+    fn url(&self, name: &str) -> Result<(&Target, String), String> {
+        let (name, fragment) = match name.split_once('#') {
+            Some((name, fragment)) => (name, Some(fragment)),
+            None => (name, None),
+        };
+        let target = self.find(name).ok_or_else(|| name.trim().to_string())?;
+
+        let url = match fragment.map(slugify).filter(|f| !f.is_empty()) {
+            Some(fragment) => format!("{}#{fragment}", target.url),
+            None => target.url.clone(),
+        };
+        Ok((target, url))
+    }
+}
+
+/// A file name without the `YYYY-MM-DD-` it starts with
+fn undated(name: &str) -> Option<&str> {
+    let (date, rest) = name.split_at_checked(11)?;
+    let is_date = date
+        .char_indices()
+        .all(|(i, c)| matches!(i, 4 | 7 | 10) == (c == '-') && (c == '-' || c.is_ascii_digit()));
+    is_date.then_some(rest)
+}
+
+/// Rewrites the cross-links of a document, remembering the first unknown article
+struct Resolver<'a> {
+    links: &'a CrossLinks,
+    unknown: Option<String>,
+}
+
+impl Resolver<'_> {
+    /// Resolves an `@article:` destination in place
+    fn destination(&mut self, destination: &mut String) {
+        let Some(name) = destination.strip_prefix(ARTICLE_SCHEME) else {
+            return;
+        };
+        match self.links.url(name) {
+            Ok((_, url)) => *destination = url,
+            Err(name) => {
+                self.unknown.get_or_insert(name);
+            }
+        }
+    }
+
+    /// The link a `[[target#fragment|label]]` stands for, if the inlines are one
+    ///
+    /// The parser reads it as a reference whose text is another reference
+    fn wiki_link(&mut self, inline: &Inline) -> Option<Inline> {
+        let Inline::LinkReference(outer) = inline else {
+            return None;
+        };
+        let [Inline::LinkReference(inner)] = outer.text.as_slice() else {
+            return None;
+        };
+
+        // the name runs up to the first `|`, the label is everything after it
+        let mut name = Vec::new();
+        let mut label = Vec::new();
+        for inline in &inner.text {
+            match inline {
+                Inline::Text { content, user_data } if label.is_empty() => {
+                    match content.split_once('|') {
+                        Some((before, after)) => {
+                            name.push(text(before));
+                            label.push(Inline::Text {
+                                content: after.to_string(),
+                                user_data: user_data.clone(),
+                            });
+                        }
+                        None => name.push(inline.clone()),
+                    }
+                }
+                _ if label.is_empty() => name.push(inline.clone()),
+                _ => label.push(inline.clone()),
+            }
+        }
+        let name = plain_text(&name);
+        if name.trim().is_empty() {
+            return None;
+        }
+
+        let (target, url) = match self.links.url(&name) {
+            Ok(found) => found,
+            Err(name) => {
+                self.unknown.get_or_insert(name);
+                return None;
+            }
+        };
+        if plain_text(&label).trim().is_empty() {
+            label = vec![text(&target.title)];
+        }
+        Some(Inline::Link(Link {
+            destination: url,
+            title: None,
+            children: label,
+            user_data: outer.user_data.clone(),
+        }))
+    }
+}
+
+impl Transform for Resolver<'_> {
+    fn block(&mut self, block: &mut Block) {
+        if let Block::Definition(definition) = block {
+            self.destination(&mut definition.destination);
+        }
+    }
+
+    fn inline(&mut self, inline: &mut Inline) {
+        if let Some(link) = self.wiki_link(inline) {
+            *inline = link;
+        }
+    }
+
+    fn link(&mut self, link: &mut Link) {
+        self.destination(&mut link.destination);
+    }
+}
+
+fn text(content: &str) -> Inline {
+    Inline::Text {
+        content: content.to_string(),
+        user_data: Default::default(),
+    }
+}
+
+/// Line a link to an unknown article is written on, preferring where it's written as a link
+fn line_of(markdown: &str, target: &str) -> usize {
+    let written = [format!("[[{target}"), format!("{ARTICLE_SCHEME}{target}")];
+    let line = markdown
+        .lines()
+        .position(|line| written.iter().any(|w| line.contains(w.as_str())))
+        .or_else(|| markdown.lines().position(|line| line.contains(target)));
+    line.unwrap_or_default() + 1
+}
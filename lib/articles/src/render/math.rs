@@ -0,0 +1,347 @@
+use super::{
+    Block, CodeBlock, Inline, Transform,
+    parser::{Fence, Lines, backticks, code_span_len, indent},
+};
+use crate::errors::ArticleError;
+use markdown_ppp::ast::CodeBlockKind;
+use pulldown_latex::{Parser, RenderConfig, Storage, config::DisplayMode, push_mathml};
+
+/// Info string of a fenced code block holding display math
+const MATH_LANGUAGE: &str = "math";
+
+/// Rewrites `$...$` and `$$...$$` math into syntax the markdown parser keeps intact
+///
+/// Math would otherwise be read as markdown, with `x_i` turning into emphasis,
+/// so inline math becomes a code span between dollars (`` $`x_i`$ ``, which
+/// may also be written directly) and `$$` blocks become `math` code fences.
+/// Code and raw HTML are left as they are. Every expression is checked here,
+/// where its line is still known
+pub(crate) fn mark(markdown: &str) -> Result<String, ArticleError> {
+    let mut marked = String::with_capacity(markdown.len());
+    let mut lines = Lines::default();
+    // open code fence, with the line it was opened on and the math inside it
+    let mut fence: Option<(Fence, usize, Vec<&str>)> = None;
+    let mut display: Option<(usize, Vec<&str>)> = None;
+
+    for (i, line) in markdown.lines().enumerate() {
+        let number = i + 1;
+        let trimmed = line.trim();
+
+        // inside a code fence only `math` fences are looked at
+        if let Some((open, start, body)) = &mut fence {
+            lines.skip();
+            let math = open.language() == Some(MATH_LANGUAGE);
+            if open.closes(line) {
+                if math {
+                    check(&body.join("\n"), *start)?;
+                }
+                fence = None;
+            } else if math {
+                body.push(line);
+            }
+            marked.push_str(line);
+            marked.push('\n');
+            continue;
+        }
+
+        // a `$$` block becomes a math fence
+        if let Some((start, body)) = &mut display {
+            lines.skip();
+            if trimmed == "$$" {
+                let expression = body.join("\n");
+                check(&expression, *start)?;
+                marked.push_str(&format!("```{MATH_LANGUAGE}\n{expression}\n```\n"));
+                display = None;
+            } else {
+                body.push(line);
+            }
+            continue;
+        }
+        if lines.verbatim(line) {
+            marked.push_str(line);
+            marked.push('\n');
+            continue;
+        }
+        if trimmed == "$$" && indent(line) <= 3 {
+            display = Some((number, Vec::new()));
+            continue;
+        }
+
+        if let Some(open) = Fence::open(line) {
+            fence = Some((open, number, Vec::new()));
+            marked.push_str(line);
+        } else {
+            marked.push_str(&mark_inline(line, number)?);
+        }
+        marked.push('\n');
+    }
+
+    // an unclosed `$$` is just text
+    if let Some((_, body)) = display {
+        marked.push_str("$$\n");
+        for line in body {
+            marked.push_str(line);
+            marked.push('\n');
+        }
+    }
+
+    Ok(marked)
+}
+
+/// Turns math into MathML, in paragraphs and `math` fences
+pub(crate) struct Math;
+
+impl Transform for Math {
+    fn block(&mut self, block: &mut Block) {
+        match block {
+            Block::CodeBlock(code) if is_math(code) => {
+                *block = Block::HtmlBlock {
+                    content: mathml(code.literal.trim_end(), DisplayMode::Block),
+                    user_data: Default::default(),
+                };
+            }
+            Block::Paragraph { content, .. } => inline_math(content),
+            Block::Heading(heading) => inline_math(&mut heading.content),
+            Block::Table(table) => table.rows.iter_mut().flatten().for_each(inline_math),
+            _ => {}
+        }
+    }
+}
+
+/// Marks the inline math of a line, leaving code spans and escapes alone
+fn mark_inline(line: &str, number: usize) -> Result<String, ArticleError> {
+    let mut marked = String::with_capacity(line.len());
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        // an escaped dollar becomes an entity, which the parser reads anywhere
+        if let Some(after) = rest.strip_prefix("\\$") {
+            marked.push_str("&#36;");
+            rest = after;
+            continue;
+        }
+
+        // other escapes and code spans are copied as they are
+        let verbatim = match c {
+            '\\' => rest.chars().nth(1).map_or(1, |e| 1 + e.len_utf8()),
+            '`' => code_span_len(rest).unwrap_or_else(|| backticks(rest)),
+            _ => 0,
+        };
+        if verbatim > 0 {
+            marked.push_str(&rest[..verbatim]);
+            rest = &rest[verbatim..];
+            continue;
+        }
+
+        if c == '$'
+            && let Some((len, delimiter, expression)) = math_span(rest)
+        {
+            check(expression, number)?;
+            let fence = "`".repeat(longest_backticks(expression) + 1);
+            marked.push_str(&format!(
+                "{delimiter}{fence} {expression} {fence}{delimiter}"
+            ));
+            rest = &rest[len..];
+            continue;
+        }
+
+        marked.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    Ok(marked)
+}
+
+/// Length, delimiter and expression of the math starting the text
+///
+/// Inline math runs to the next unescaped `$`. It can't start or end with a
+/// space and its closing `$` can't be followed by a letter or digit, so amounts
+/// like `$5 and $10` and variables like `$HOME/$USER` stay text
+fn math_span(text: &str) -> Option<(usize, &str, &str)> {
+    if let Some(after) = text.strip_prefix("$$") {
+        let end = after.find("$$")?;
+        let expression = after[..end].trim();
+        return (!expression.is_empty()).then_some((end + 4, "$$", expression));
+    }
+
+    let after = &text[1..];
+    // already marked, `$` around a code span
+    if after.starts_with('`')
+        && let Some(len) = code_span_len(after)
+        && after[len..].starts_with('$')
+    {
+        let span = &after[..len];
+        let expression = span.trim_matches('`').trim();
+        return Some((len + 2, "$", expression)).filter(|_| !expression.is_empty());
+    }
+
+    if after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    // the math ends before any code span
+    let after = &after[..after.find('`').unwrap_or(after.len())];
+    let (end, _) = after
+        .match_indices('$')
+        .find(|(end, _)| !after[..*end].ends_with('\\'))?;
+    let before = after[..end].chars().next_back();
+    let next = after[end + 1..].chars().next();
+    let valid =
+        before.is_some_and(|b| !b.is_whitespace()) && !next.is_some_and(char::is_alphanumeric);
+    valid.then_some((end + 2, "$", &after[..end]))
+}
+
+/// Longest run of backticks in the text
+fn longest_backticks(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Checks that an expression parses, reporting the line it's on
+fn check(expression: &str, line: usize) -> Result<(), ArticleError> {
+    let storage = Storage::new();
+    let error = Parser::new(expression, &storage).find_map(Result::err);
+    match error {
+        Some(error) => {
+            let message = error.to_string();
+            let message = message.lines().next().unwrap_or_default();
+            Err(ArticleError::Math {
+                line,
+                message: message
+                    .strip_prefix("parsing error: ")
+                    .unwrap_or(message)
+                    .to_string(),
+            })
+        }
+        None => Ok(()),
+    }
+}
+
+/// Renders an expression to MathML, keeping the source as an annotation
+fn mathml(expression: &str, display_mode: DisplayMode) -> String {
+    let storage = Storage::new();
+    let config = RenderConfig {
+        display_mode,
+        annotation: Some(expression),
+        ..Default::default()
+    };
+    let mut html = String::new();
+    // writing to a string can't fail, and errors were reported by `mark`
+    let _ = push_mathml(&mut html, Parser::new(expression, &storage), config);
+    html
+}
+
+fn is_math(code: &CodeBlock) -> bool {
+    matches!(&code.kind, CodeBlockKind::Fenced { info: Some(info) }
+        if info.split_whitespace().next() == Some(MATH_LANGUAGE))
+}
+
+/// Replaces code spans between dollars with MathML, looking inside formatting too
+fn inline_math(inlines: &mut Vec<Inline>) {
+    let mut i = 0;
+    while i < inlines.len() {
+        let between = i > 0 && i + 1 < inlines.len();
+        match &mut inlines[i] {
+            Inline::Emphasis { content, .. }
+            | Inline::Strong { content, .. }
+            | Inline::Strikethrough { content, .. } => inline_math(content),
+            Inline::Link(link) => inline_math(&mut link.children),
+            Inline::Code { .. } if between => {
+                if let Some(html) = math_code(&mut inlines[i - 1..=i + 1]) {
+                    inlines[i] = Inline::Html {
+                        content: html,
+                        user_data: Default::default(),
+                    };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    inlines.retain(|inline| !matches!(inline, Inline::Text { content, .. } if content.is_empty()));
+}
+
+/// Renders a code span between dollars, taking the dollars off the text around it
+fn math_code(around: &mut [Inline]) -> Option<String> {
+    let [
+        Inline::Text {
+            content: before, ..
+        },
+        Inline::Code { content: code, .. },
+        Inline::Text { content: after, .. },
+    ] = around
+    else {
+        return None;
+    };
+
+    let (delimiter, mode) = if before.ends_with("$$") && after.starts_with("$$") {
+        ("$$", DisplayMode::Block)
+    } else if before.ends_with('$') && after.starts_with('$') {
+        ("$", DisplayMode::Inline)
+    } else {
+        return None;
+    };
+
+    before.truncate(before.len() - delimiter.len());
+    after.replace_range(..delimiter.len(), "");
+    Some(mathml(code.trim(), mode))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::mark;
+
+    #[test]
+    fn marks_inline_math() {
+        assert_eq!(mark("A $x_i$ term").unwrap(), "A $` x_i `$ term\n");
+    }
+
+    #[test]
+    fn leaves_indented_code_alone() {
+        let markdown = "Paths:\n\n    echo $HOME/$USER\n";
+        assert_eq!(mark(markdown).unwrap(), markdown);
+    }
+
+    #[test]
+    fn marks_indented_paragraph_text() {
+        let markdown = "A paragraph\n    with $x$\n\n- an item\n\n    with $y$\n";
+        assert_eq!(
+            mark(markdown).unwrap(),
+            "A paragraph\n    with $` x `$\n\n- an item\n\n    with $` y `$\n"
+        );
+    }
+
+    #[test]
+    fn leaves_html_blocks_alone() {
+        let markdown = "<div>$a$ and $b$</div>\n\n<!--\n$c$\n-->\n\nThen $d$\n";
+        assert_eq!(
+            mark(markdown).unwrap(),
+            "<div>$a$ and $b$</div>\n\n<!--\n$c$\n-->\n\nThen $` d `$\n"
+        );
+    }
+
+    #[test]
+    fn leaves_amounts_as_text() {
+        assert_eq!(
+            mark("It costs $5 and $10, or $x$ per item").unwrap(),
+            "It costs $5 and $10, or $` x `$ per item\n"
+        );
+        assert_eq!(
+            mark("From $5, for $n$ items").unwrap(),
+            "From $5, for $` n `$ items\n"
+        );
+    }
+
+    #[test]
+    fn leaves_shell_variables_as_text() {
+        assert_eq!(mark("Run ls $HOME/$USER").unwrap(), "Run ls $HOME/$USER\n");
+    }
+
+    #[test]
+    fn leaves_unclosed_dollars_as_text() {
+        assert_eq!(mark("Only $5 and $x").unwrap(), "Only $5 and $x\n");
+    }
+
+    #[test]
+    fn marks_math_after_inline_html() {
+        assert_eq!(mark("<span>$a$</span>").unwrap(), "<span>$` a `$</span>\n");
+    }
+}
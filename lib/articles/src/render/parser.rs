@@ -0,0 +1,490 @@
+use super::{Options, admonitions, shortcodes};
+use crate::errors::ArticleError;
+use markdown_ppp::{
+    ast,
+    parser::{MarkdownParserState, config::ElementBehavior, config::MarkdownParserConfig},
+};
+use nom::{IResult, Parser as _, branch::alt};
+use std::{cell::RefCell, rc::Rc};
+
+/// Parser for the block syntax markdown-ppp doesn't know about
+type BlockParser =
+    Rc<RefCell<Box<dyn for<'a> FnMut(&'a str) -> IResult<&'a str, Vec<ast::Block>>>>>;
+
+/// Parser for the inline syntax markdown-ppp doesn't know about
+type InlineParser =
+    Rc<RefCell<Box<dyn for<'a> FnMut(&'a str) -> IResult<&'a str, Vec<ast::Inline>>>>>;
+
+/// Markdown parser with our own blocks added, which parse their content with it in turn
+#[derive(Clone)]
+pub(crate) struct Parser {
+    pub options: Options,
+    /// First error hit by one of our block parsers, nom errors can't carry it
+    error: Rc<RefCell<Option<ArticleError>>>,
+}
+
+impl Parser {
+    pub fn new(options: &Options) -> Self {
+        Parser {
+            options: options.clone(),
+            error: Default::default(),
+        }
+    }
+
+    /// Parses markdown, including the content of our blocks
+    pub fn parse(&self, markdown: &str) -> Result<ast::Document, ArticleError> {
+        let state = MarkdownParserState::with_config(self.config());
+        let parsed = markdown_ppp::parser::parse_markdown(state, markdown)
+            .map_err(|e| ArticleError::ContentParse(e.map(|e| e.code).to_string()));
+
+        // an error from our blocks explains the failure better than nom can
+        match self.error.borrow_mut().take() {
+            Some(error) => Err(error),
+            None => parsed,
+        }
+    }
+
+    /// Records an error and stops parsing
+    pub fn fail<'a, T>(&self, input: &'a str, error: ArticleError) -> IResult<&'a str, T> {
+        self.error.borrow_mut().get_or_insert(error);
+        Err(nom::Err::Failure(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Fail,
+        )))
+    }
+
+    /// Parser settings for the options
+    fn config(&self) -> MarkdownParserConfig {
+        fn behavior<T>(enabled: bool) -> ElementBehavior<T> {
+            if enabled {
+                ElementBehavior::Parse
+            } else {
+                ElementBehavior::Ignore
+            }
+        }
+
+        let options = &self.options;
+        MarkdownParserConfig::default()
+            .with_block_table_behavior(behavior(options.tables))
+            .with_block_footnote_definition_behavior(behavior(options.footnotes))
+            .with_inline_footnote_reference_behavior(behavior(options.footnotes))
+            .with_inline_strikethrough_behavior(behavior(options.strikethrough))
+            .with_custom_block_parser(self.blocks())
+            .with_custom_inline_parser(self.inlines())
+    }
+
+    /// Our blocks, tried after everything but paragraphs
+    fn blocks(&self) -> BlockParser {
+        let parser = self.clone();
+        Rc::new(RefCell::new(Box::new(move |input| {
+            alt((
+                |input| admonitions::container(input, &parser),
+                |input| shortcodes::shortcode(input, &parser),
+                html_block,
+            ))
+            .parse(input)
+        })))
+    }
+
+    /// Our inlines, tried where nothing else matches
+    fn inlines(&self) -> InlineParser {
+        Rc::new(RefCell::new(Box::new(inline_html)))
+    }
+}
+
+/// A raw HTML block started by a tag alone on its line, running to the next blank line
+///
+/// markdown-ppp only knows the blocks started by HTML block tags, and reads
+/// these as paragraph text
+fn html_block(input: &str) -> IResult<&str, Vec<ast::Block>> {
+    let (line, _) = split_line(input);
+    if indent(line) > 3 || !matches!(html_start(line.trim_start()), Some(HtmlEnd::BlankLine)) {
+        return mismatch(input);
+    }
+
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (line, after) = split_line(rest);
+        if line.trim().is_empty() {
+            break;
+        }
+        rest = after;
+    }
+    let html = &input[..input.len() - rest.len()];
+    Ok((rest, vec![ast::Block::HtmlBlock(html.to_string())]))
+}
+
+/// Raw HTML starting the text, a tag, a closing tag or a comment
+///
+/// markdown-ppp reads inline HTML as text, which would be escaped
+fn inline_html(input: &str) -> IResult<&str, Vec<ast::Inline>> {
+    let Some(rest) = input.strip_prefix('<') else {
+        return mismatch(input);
+    };
+    let len = match rest.strip_prefix("!--") {
+        Some(comment) => comment.find("-->").map(|end| end + 7),
+        None => {
+            let tag = rest.strip_prefix('/').unwrap_or(rest);
+            let name = tag
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+                .unwrap_or(tag.len());
+            let named = tag.starts_with(|c: char| c.is_ascii_alphabetic())
+                && tag[name..].starts_with([' ', '\t', '\n', '/', '>']);
+            named.then(|| tag_end(input)).flatten()
+        }
+    };
+
+    match len {
+        Some(len) => Ok((
+            &input[len..],
+            vec![ast::Inline::Html(input[..len].to_string())],
+        )),
+        None => mismatch(input),
+    }
+}
+
+/// Error that makes nom try the next parser
+pub(crate) fn mismatch<T>(input: &str) -> IResult<&str, T> {
+    Err(nom::Err::Error(nom::error::Error::new(
+        input,
+        nom::error::ErrorKind::Tag,
+    )))
+}
+
+/// Splits off the first line, without its line ending
+pub(crate) fn split_line(text: &str) -> (&str, &str) {
+    match text.find('\n') {
+        Some(end) => (text[..end].trim_end_matches('\r'), &text[end + 1..]),
+        None => (text, ""),
+    }
+}
+
+/// An open code fence
+pub(crate) struct Fence<'a> {
+    marker: char,
+    len: usize,
+    info: &'a str,
+}
+
+impl<'a> Fence<'a> {
+    /// Reads the opening line of a fence
+    pub fn open(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = trimmed.chars().take_while(|c| *c == marker).count();
+        let info = trimmed[len..].trim();
+        if indent(line) > 3 || len < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+
+        Some(Fence { marker, len, info })
+    }
+
+    /// First word of the info string
+    pub fn language(&self) -> Option<&'a str> {
+        self.info.split_whitespace().next()
+    }
+
+    /// Whether the line closes the fence
+    pub fn closes(&self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        let len = trimmed.chars().take_while(|c| *c == self.marker).count();
+        len >= self.len && trimmed[len..].trim().is_empty()
+    }
+}
+
+/// Length of a code span starting the text, if it's closed on the same line
+pub(crate) fn code_span_len(text: &str) -> Option<usize> {
+    let open = backticks(text);
+    let mut search = open;
+    while let Some(offset) = text[search..].find('`') {
+        let start = search + offset;
+        let run = backticks(&text[start..]);
+        if run == open {
+            return Some(start + run);
+        }
+        search = start + run;
+    }
+    None
+}
+
+/// Number of backticks starting the text
+pub(crate) fn backticks(text: &str) -> usize {
+    text.chars().take_while(|c| *c == '`').count()
+}
+
+/// Number of spaces a line is indented by
+pub(crate) fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+/// Tags that start a raw HTML block running to the next blank line
+const HTML_BLOCK_TAGS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+/// What ends a raw HTML block
+enum HtmlEnd {
+    /// A line containing any of these, compared in lowercase
+    Text(&'static [&'static str]),
+    BlankLine,
+}
+
+/// Follows the blocks of a document line by line, telling markdown text from
+/// indented code and raw HTML
+///
+/// Pre-passes rewrite the markdown before the parser sees it, and have to
+/// leave alone what the parser keeps verbatim. Code fences are left to
+/// [`Fence`], and block quotes aren't looked into
+#[derive(Default)]
+pub(crate) struct Lines {
+    /// What ends the raw HTML block being read
+    html: Option<HtmlEnd>,
+    /// Content indent of the list item being read
+    item: Option<usize>,
+    /// Whether the previous line is part of a paragraph, which indented code can't interrupt
+    paragraph: bool,
+    blank: bool,
+}
+
+impl Lines {
+    /// Reads the next line, telling whether it's indented code or raw HTML
+    pub fn verbatim(&mut self, line: &str) -> bool {
+        match &self.html {
+            Some(HtmlEnd::Text(ends)) => {
+                if ends
+                    .iter()
+                    .any(|end| line.to_ascii_lowercase().contains(end))
+                {
+                    self.html = None;
+                }
+                return true;
+            }
+            Some(HtmlEnd::BlankLine) if !line.trim().is_empty() => return true,
+            Some(HtmlEnd::BlankLine) => self.html = None,
+            None => {}
+        }
+
+        if line.trim().is_empty() {
+            self.paragraph = false;
+            self.blank = true;
+            return false;
+        }
+        let blank = std::mem::take(&mut self.blank);
+
+        // a list item ends at text indented less than its content, unless it's a lazy continuation
+        let indent = indent(line);
+        if self.item.is_some_and(|item| indent < item) && (blank || !self.paragraph) {
+            self.item = None;
+        }
+        let base = self.item.unwrap_or(0);
+        if indent >= base + 4 {
+            return !self.paragraph;
+        }
+
+        let trimmed = line.trim_start();
+        if let Some(content) = list_item(trimmed) {
+            self.item = Some(indent + content);
+            self.paragraph = true;
+            return false;
+        }
+        if let Some(end) = html_start(trimmed) {
+            self.paragraph = false;
+            let closed = match end {
+                HtmlEnd::Text(ends) => ends
+                    .iter()
+                    .any(|end| trimmed.to_ascii_lowercase()[1..].contains(end)),
+                HtmlEnd::BlankLine => false,
+            };
+            if !closed {
+                self.html = Some(end);
+            }
+            return true;
+        }
+
+        self.paragraph = !trimmed.starts_with('#');
+        false
+    }
+
+    /// Notes a line the caller reads itself, such as a code fence
+    pub fn skip(&mut self) {
+        self.paragraph = false;
+        self.blank = false;
+    }
+}
+
+/// Width of a list marker and the spaces after it, if the line starts a list item
+fn list_item(line: &str) -> Option<usize> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker = match line[digits..].chars().next()? {
+        '-' | '*' | '+' if digits == 0 => 1,
+        '.' | ')' if (1..=9).contains(&digits) => digits + 1,
+        _ => return None,
+    };
+
+    let after = &line[marker..];
+    let spaces = after.len() - after.trim_start_matches(' ').len();
+    match spaces {
+        _ if after.trim().is_empty() => Some(marker + 1),
+        0 => None,
+        // five or more spaces start indented code in the item, its content is one space in
+        1..=4 => Some(marker + spaces),
+        _ => Some(marker + 1),
+    }
+}
+
+/// How a raw HTML block starting the line ends, if one does
+///
+/// Any tag alone on a line starts one too. CommonMark doesn't let those
+/// interrupt a paragraph, but markdown-ppp ends paragraphs at any line our
+/// block parsers accept
+fn html_start(line: &str) -> Option<HtmlEnd> {
+    let rest = line.strip_prefix('<')?;
+    let lower = rest.to_ascii_lowercase();
+    if lower.starts_with("!--") {
+        return Some(HtmlEnd::Text(&["-->"]));
+    }
+    if lower.starts_with('?') {
+        return Some(HtmlEnd::Text(&["?>"]));
+    }
+    if lower.starts_with("![cdata[") {
+        return Some(HtmlEnd::Text(&["]]>"]));
+    }
+    if rest.starts_with('!') && rest[1..].starts_with(|c: char| c.is_ascii_uppercase()) {
+        return Some(HtmlEnd::Text(&[">"]));
+    }
+
+    let closing = lower.starts_with('/');
+    let tag = lower.strip_prefix('/').unwrap_or(&lower);
+    let name_len = tag
+        .find(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+        .unwrap_or(tag.len());
+    let (name, after) = tag.split_at(name_len);
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let ends_name = after.is_empty() || after.starts_with([' ', '\t', '>']);
+
+    if !closing && ends_name && ["script", "pre", "style", "textarea"].contains(&name) {
+        return Some(HtmlEnd::Text(&[
+            "</script>",
+            "</pre>",
+            "</style>",
+            "</textarea>",
+        ]));
+    }
+    if (ends_name || after.starts_with("/>")) && HTML_BLOCK_TAGS.contains(&name) {
+        return Some(HtmlEnd::BlankLine);
+    }
+    let alone =
+        (ends_name || after.starts_with('/')) && tag_end(line) == Some(line.trim_end().len());
+    alone.then_some(HtmlEnd::BlankLine)
+}
+
+/// Length of the tag starting the text, `>` in quoted attribute values doesn't end it
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::render::{Options, render};
+
+    fn html(markdown: &str) -> String {
+        render(markdown, &Options::default(), &mut []).unwrap()
+    }
+
+    #[test]
+    fn reads_a_lone_tag_as_an_html_block() {
+        assert_eq!(
+            html("<img src=\"a.png\" alt=\"A\">\n\nText\n"),
+            "<img src=\"a.png\" alt=\"A\">\n<p>Text</p>"
+        );
+    }
+
+    #[test]
+    fn keeps_inline_html() {
+        assert_eq!(
+            html("Text <img src=\"a.png\" /> and <b>bold</b>, a < b.\n"),
+            "<p>Text <img src=\"a.png\" /> and <b>bold</b>, a &lt; b.</p>"
+        );
+    }
+
+    #[test]
+    fn lone_tags_interrupt_paragraphs() {
+        assert_eq!(
+            html("Text\n<img src=\"$a$.png\">\n"),
+            "<p>Text</p>\n<img src=\"$a$.png\">"
+        );
+    }
+}
@@ -0,0 +1,208 @@
+use super::{
+    Block, Document, Options, emit,
+    parser::{Parser, mismatch, split_line},
+};
+use crate::{code::words, errors::ArticleError};
+use markdown_ppp::ast::{self, GitHubAlert, GitHubAlertType, generic::GitHubAlertNode};
+use nom::IResult;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A shortcode as written in an article
+///
+/// ```markdown
+/// {{< video src="/assets/demo.mp4" autoplay />}}
+///
+/// {{< aside title="Why not WebRTC?" >}}
+/// Any *markdown* can go between the tags
+/// {{< /aside >}}
+/// ```
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Shortcode {
+    pub name: String,
+    /// Named arguments, flags without a value are `"true"`
+    pub args: BTreeMap<String, String>,
+    /// Content between the opening and closing tags, rendered to HTML
+    pub inner: Option<String>,
+}
+
+/// Renders shortcodes to HTML, provided by whatever builds the site
+pub trait Shortcodes: Send + Sync {
+    /// Renders a shortcode, or explains why it can't be
+    fn render(&self, shortcode: &Shortcode) -> Result<String, String>;
+}
+
+/// A `{{< ... >}}` tag on a line of its own
+struct Tag<'a> {
+    name: &'a str,
+    args: &'a str,
+    closing: bool,
+    self_closing: bool,
+}
+
+impl<'a> Tag<'a> {
+    fn parse(line: &'a str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        if line.len() - trimmed.len() > 3 {
+            return None;
+        }
+        let inner = trimmed
+            .trim_end()
+            .strip_prefix("{{<")?
+            .strip_suffix(">}}")?
+            .trim();
+        let (inner, self_closing) = match inner.strip_suffix('/') {
+            Some(inner) => (inner.trim_end(), true),
+            None => (inner, false),
+        };
+        let (inner, closing) = match inner.strip_prefix('/') {
+            Some(inner) => (inner.trim_start(), true),
+            None => (inner, false),
+        };
+        let (name, args) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        valid.then_some(Tag {
+            name,
+            args,
+            closing,
+            self_closing,
+        })
+    }
+}
+
+/// Parses a shortcode on lines of its own
+///
+/// Without a closing tag the shortcode has no content, a `/>}}` ending makes
+/// that explicit. The content is parsed into blocks that stay in the document,
+/// so transforms see it, and the shortcode is only rendered by [`expand`].
+/// Shortcodes are only recognized when the options have something to render
+/// them with
+pub(crate) fn shortcode<'a>(input: &'a str, parser: &Parser) -> IResult<&'a str, Vec<ast::Block>> {
+    if parser.options.shortcodes.is_none() {
+        return mismatch(input);
+    }
+    let (first, after_open) = split_line(input);
+    let Some(open) = Tag::parse(first).filter(|tag| !tag.closing) else {
+        return mismatch(input);
+    };
+
+    // content up to the matching closing tag, if there is one
+    let mut rest = after_open;
+    let mut body = Vec::new();
+    let mut closed = false;
+    let mut depth = 0;
+    while !open.self_closing && !rest.is_empty() {
+        let (line, next) = split_line(rest);
+        rest = next;
+        match Tag::parse(line) {
+            Some(tag) if tag.name == open.name && tag.closing => {
+                if depth == 0 {
+                    closed = true;
+                    break;
+                }
+                depth -= 1;
+            }
+            Some(tag) if tag.name == open.name && !tag.self_closing => depth += 1,
+            _ => {}
+        }
+        body.push(line);
+    }
+    if !closed {
+        rest = after_open;
+    }
+
+    let blocks = if closed {
+        match parser.parse(&body.join("\n")) {
+            Ok(document) => document.blocks,
+            Err(error) => return parser.fail(input, error),
+        }
+    } else {
+        Vec::new()
+    };
+
+    // the tag waits in an alert of its own, self-closing when there's no content
+    let end = if closed { ">}}" } else { "/>}}" };
+    Ok((
+        rest,
+        vec![ast::Block::GitHubAlert(GitHubAlert {
+            alert_type: GitHubAlertType::Custom(format!("{{{{< {} {} {end}", open.name, open.args)),
+            blocks,
+        })],
+    ))
+}
+
+/// Renders the shortcodes of a document in place, innermost first
+///
+/// Runs once every transform is done with the document, the content of a
+/// shortcode is handed to it as HTML
+pub fn expand(document: &mut Document, options: &Options) -> Result<(), ArticleError> {
+    match &options.shortcodes {
+        Some(shortcodes) => expand_blocks(&mut document.blocks, shortcodes.as_ref()),
+        None => Ok(()),
+    }
+}
+
+fn expand_blocks(blocks: &mut [Block], shortcodes: &dyn Shortcodes) -> Result<(), ArticleError> {
+    for block in blocks {
+        match block {
+            Block::BlockQuote { blocks: inner, .. }
+            | Block::GitHubAlert(GitHubAlertNode { blocks: inner, .. }) => {
+                expand_blocks(inner, shortcodes)?
+            }
+            Block::List(list) => {
+                for item in &mut list.items {
+                    expand_blocks(&mut item.blocks, shortcodes)?;
+                }
+            }
+            Block::FootnoteDefinition(footnote) => expand_blocks(&mut footnote.blocks, shortcodes)?,
+            _ => {}
+        }
+
+        let Block::GitHubAlert(alert) = block else {
+            continue;
+        };
+        let GitHubAlertType::Custom(tag) = &alert.alert_type else {
+            continue;
+        };
+        let Some(tag) = Tag::parse(tag) else {
+            continue;
+        };
+
+        let shortcode = Shortcode {
+            name: tag.name.to_string(),
+            args: args(tag.args),
+            inner: (!tag.self_closing).then(|| {
+                emit(&Document {
+                    blocks: std::mem::take(&mut alert.blocks),
+                    user_data: Default::default(),
+                })
+            }),
+        };
+        let html = shortcodes
+            .render(&shortcode)
+            .map_err(|message| ArticleError::Shortcode {
+                name: shortcode.name,
+                message,
+            })?;
+        *block = Block::HtmlBlock {
+            content: html,
+            user_data: Default::default(),
+        };
+    }
+    Ok(())
+}
+
+/// Parses `key="value"` arguments, bare words are flags
+fn args(text: &str) -> BTreeMap<String, String> {
+    words(text)
+        .into_iter()
+        .map(|word| {
+            let (key, value) = word.split_once('=').unwrap_or((&word, "true"));
+            (key.to_string(), value.trim_matches(['"', '\'']).to_string())
+        })
+        .collect()
+}
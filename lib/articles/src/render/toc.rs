@@ -0,0 +1,99 @@
+use super::{Heading, Inline, Transform, plain_text};
+use crate::slug::slugify;
+use markdown_ppp::ast::{HeadingKind, SetextHeading};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// Id given to headings that have nothing to slugify
+const FALLBACK_ID: &str = "section";
+
+/// A heading in the table of contents, with the headings below it
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub id: String,
+    pub title: String,
+    pub children: Vec<TocEntry>,
+}
+
+/// Gives every heading a unique id and a hover anchor, collecting the table of contents
+///
+/// Ids come from the heading text (`## Setting up` becomes `setting-up`), a
+/// repeated heading gets a counter (`setting-up-1`), and ids set by an
+/// earlier transform are kept
+#[derive(Debug, Default)]
+pub struct Anchors {
+    ids: HashSet<String>,
+    /// Headings in document order, nested by `toc`
+    headings: Vec<TocEntry>,
+}
+
+impl Anchors {
+    /// The headings nested by level, a heading belongs to the closest one above it
+    /// with a lower level
+    pub fn toc(&self) -> Vec<TocEntry> {
+        let mut toc = Vec::new();
+        for heading in &self.headings {
+            insert(&mut toc, heading.clone());
+        }
+        toc
+    }
+
+    /// Makes an id unique within the document
+    fn unique(&mut self, id: String) -> String {
+        let mut unique = id.clone();
+        let mut count = 0;
+        while self.ids.contains(&unique) {
+            count += 1;
+            unique = format!("{id}-{count}");
+        }
+        self.ids.insert(unique.clone());
+        unique
+    }
+}
+
+impl Transform for Anchors {
+    fn heading(&mut self, heading: &mut Heading) {
+        let title = plain_text(&heading.content);
+        let id = match heading.user_data.id.take() {
+            Some(id) => id,
+            None => match slugify(&title) {
+                slug if slug.is_empty() => FALLBACK_ID.to_string(),
+                slug => slug,
+            },
+        };
+        let id = self.unique(id);
+
+        heading.content.push(Inline::Html {
+            content: format!(
+                "<a class=\"anchor\" href=\"#{id}\" aria-hidden=\"true\" tabindex=\"-1\">#</a>"
+            ),
+            user_data: Default::default(),
+        });
+        heading.user_data.id = Some(id.clone());
+
+        self.headings.push(TocEntry {
+            level: level(heading),
+            id,
+            title,
+            children: Vec::new(),
+        });
+    }
+}
+
+/// Level of a heading from 1 to 6
+pub(crate) fn level(heading: &Heading) -> u8 {
+    match heading.kind {
+        HeadingKind::Atx(level) => level.clamp(1, 6),
+        HeadingKind::Setext(SetextHeading::Level1) => 1,
+        HeadingKind::Setext(SetextHeading::Level2) => 2,
+    }
+}
+
+/// Adds an entry under the last entry of a lower level, or at the end
+fn insert(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+    match entries.last_mut() {
+        Some(last) if last.level < entry.level => insert(&mut last.children, entry),
+        _ => entries.push(entry),
+    }
+}
@@ -0,0 +1,82 @@
+use super::{Block, CodeBlock, Document, Heading, Image, Inline, Link};
+use markdown_ppp::ast::generic::GitHubAlertNode;
+
+/// Rewrites parts of a document before it's turned into HTML
+///
+/// Every method is called once per matching node, parents before their
+/// children, and defaults to leaving the node alone. `block` and `inline` see
+/// every node and may replace it outright, the others are shortcuts for the
+/// node types most transforms care about
+pub trait Transform {
+    fn block(&mut self, _block: &mut Block) {}
+    fn heading(&mut self, _heading: &mut Heading) {}
+    fn code_block(&mut self, _code: &mut CodeBlock) {}
+    fn inline(&mut self, _inline: &mut Inline) {}
+    fn link(&mut self, _link: &mut Link) {}
+    fn image(&mut self, _image: &mut Image) {}
+    /// Called once every node has been visited
+    fn finish(&mut self, _document: &mut Document) {}
+}
+
+/// Runs a transform over a whole document
+pub fn apply(transform: &mut dyn Transform, document: &mut Document) {
+    visit_blocks(transform, &mut document.blocks);
+    transform.finish(document);
+}
+
+/// Visits a list of blocks and everything inside them
+fn visit_blocks(transform: &mut dyn Transform, blocks: &mut [Block]) {
+    for block in blocks {
+        transform.block(block);
+        match block {
+            Block::Paragraph { content, .. } => visit_inlines(transform, content),
+            Block::Heading(heading) => {
+                transform.heading(heading);
+                visit_inlines(transform, &mut heading.content);
+            }
+            Block::CodeBlock(code) => transform.code_block(code),
+            Block::BlockQuote { blocks: inner, .. }
+            | Block::GitHubAlert(GitHubAlertNode { blocks: inner, .. }) => {
+                visit_blocks(transform, inner)
+            }
+            Block::List(list) => {
+                for item in &mut list.items {
+                    visit_blocks(transform, &mut item.blocks);
+                }
+            }
+            Block::FootnoteDefinition(footnote) => visit_blocks(transform, &mut footnote.blocks),
+            Block::Table(table) => {
+                for cell in table.rows.iter_mut().flatten() {
+                    visit_inlines(transform, cell);
+                }
+            }
+            Block::Definition(definition) => visit_inlines(transform, &mut definition.label),
+            Block::ThematicBreak { .. } | Block::HtmlBlock { .. } | Block::Empty { .. } => {}
+        }
+    }
+}
+
+/// Visits a list of inlines and everything inside them
+fn visit_inlines(transform: &mut dyn Transform, inlines: &mut [Inline]) {
+    for inline in inlines {
+        transform.inline(inline);
+        match inline {
+            Inline::Link(link) => {
+                transform.link(link);
+                visit_inlines(transform, &mut link.children);
+            }
+            Inline::Image(image) => transform.image(image),
+            Inline::LinkReference(reference) => visit_inlines(transform, &mut reference.text),
+            Inline::Emphasis { content, .. }
+            | Inline::Strong { content, .. }
+            | Inline::Strikethrough { content, .. } => visit_inlines(transform, content),
+            Inline::Text { .. }
+            | Inline::LineBreak { .. }
+            | Inline::Code { .. }
+            | Inline::Html { .. }
+            | Inline::Autolink { .. }
+            | Inline::FootnoteReference { .. }
+            | Inline::Empty { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use crate::Article;
+
+/// Articles sharing a `series_key`, ordered by their `series_pos`
+#[derive(Debug, Clone)]
+pub struct Series<'a> {
+    pub key: &'a str,
+    pub parts: Vec<&'a Article>,
+}
+
+impl<'a> Series<'a> {
+    /// Groups articles into their series, ordered by series key
+    ///
+    /// Parts without a position are placed after the positioned ones, by date
+    pub fn group(articles: &'a [Article]) -> Vec<Series<'a>> {
+        let mut series: Vec<Series> = Vec::new();
+        for article in articles {
+            let Some(key) = article.series_key.as_deref() else {
+                continue;
+            };
+
+            match series.iter_mut().find(|s| s.key == key) {
+                Some(existing) => existing.parts.push(article),
+                None => series.push(Series {
+                    key,
+                    parts: vec![article],
+                }),
+            }
+        }
+
+        for s in series.iter_mut() {
+            s.parts
+                .sort_by_key(|a| (a.series_pos.unwrap_or(u32::MAX), a.date));
+        }
+        series.sort_by_key(|s| s.key);
+
+        series
+    }
+
+    /// Human readable title derived from the series key
+    pub fn title(&self) -> String {
+        self.key.replace(['-', '_'], " ")
+    }
+
+    /// Zero-based position of the article within the series
+    pub fn index_of(&self, article: &Article) -> Option<usize> {
+        self.parts.iter().position(|p| std::ptr::eq(*p, article))
+    }
+}
@@ -0,0 +1,61 @@
+use deunicode::deunicode;
+
+/// Turns arbitrary text into a lowercase, ascii-only url segment
+///
+/// Non-ascii characters are transliterated (`Grüße` becomes `grusse`), and
+/// every run of anything other than letters and digits collapses into a
+/// single `-`, so `What's new: Rust 2024?` becomes `what-s-new-rust-2024`
+pub fn slugify(text: &str) -> String {
+    let mut slug = String::with_capacity(text.len());
+    let mut pending_separator = false;
+
+    for c in deunicode(text).chars() {
+        if c.is_ascii_alphanumeric() {
+            // only separate words, never lead with a separator
+            if pending_separator && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_separator = false;
+            slug.push(c.to_ascii_lowercase());
+        } else {
+            pending_separator = true;
+        }
+    }
+
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slugify;
+
+    #[test]
+    fn lowercases_words() {
+        assert_eq!(slugify("Playing With Gleam"), "playing-with-gleam");
+    }
+
+    #[test]
+    fn transliterates_to_ascii() {
+        assert_eq!(slugify("Grüße aus Köln"), "grusse-aus-koln");
+        assert_eq!(slugify("Café Déjà Vu"), "cafe-deja-vu");
+    }
+
+    #[test]
+    fn collapses_punctuation() {
+        assert_eq!(slugify("What's new: Rust 2024?"), "what-s-new-rust-2024");
+        assert_eq!(
+            slugify("Custom static site generator - Handlebars & SCSS"),
+            "custom-static-site-generator-handlebars-scss"
+        );
+        assert_eq!(
+            slugify("  --leading and trailing--  "),
+            "leading-and-trailing"
+        );
+    }
+
+    #[test]
+    fn empties_titles_without_letters_or_digits() {
+        assert_eq!(slugify(""), "");
+        assert_eq!(slugify("?!& --"), "");
+    }
+}
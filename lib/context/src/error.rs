@@ -2,8 +2,16 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum SneakyContextError {
-    #[error("TOML deserialization error: {0}")]
-    Toml(#[from] toml::de::Error),
-    #[error("IO error: {0}")]
-    IO(#[from] std::io::Error),
+    #[error("TOML deserialization error in {path}: {source}")]
+    Toml {
+        path: String,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("IO error reading {path}: {source}")]
+    IO {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
 }
@@ -1,24 +1,156 @@
 use base64::{Engine, engine::general_purpose};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 pub use crate::error::SneakyContextError;
 mod error;
 
 /// Context for all sneaky crow related functionality
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct SneakyContext {
     pub me: SneakyMeta,
+    pub site: SneakySite,
+    pub web: SneakyWebConfig,
+    pub markdown: SneakyMarkdownConfig,
     pub build_info: SneakyBuildInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct SneakyMeta {
     pub name: String,
     pub email: String,
     pub title: String,
 }
 
+impl Default for SneakyMeta {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_NAME.to_string(),
+            email: DEFAULT_EMAIL.to_string(),
+            title: DEFAULT_TITLE.to_string(),
+        }
+    }
+}
+
+/// Metadata about the published website, read from the `[site]` section
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SneakySite {
+    /// Absolute URL the site is published at, used for feeds and permalinks
+    pub base_url: String,
+    pub title: String,
+    pub description: String,
+}
+
+impl Default for SneakySite {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_BASE_URL.to_string(),
+            title: DEFAULT_SITE_TITLE.to_string(),
+            description: DEFAULT_SITE_DESCRIPTION.to_string(),
+        }
+    }
+}
+
+impl SneakySite {
+    /// Joins a site-relative path onto the base url
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        )
+    }
+}
+
+/// Configuration for building and serving the website, read from the `[web]` section
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SneakyWebConfig {
+    /// Host the dev server binds to
+    pub host: String,
+    /// Port the dev server listens on
+    pub port: u16,
+    /// Directory the site is built into
+    pub out: PathBuf,
+    /// Directory the articles are read from
+    pub posts: PathBuf,
+    /// Directory containing the handlebars templates
+    pub templates: PathBuf,
+    /// Directory of static assets copied into the build
+    pub assets: PathBuf,
+    /// Number of posts per page of the blog index, 0 disables pagination
+    pub page_size: usize,
+    /// Directory processed images are kept in between builds
+    pub cache: PathBuf,
+}
+
+impl Default for SneakyWebConfig {
+    fn default() -> Self {
+        Self {
+            host: DEFAULT_WEB_HOST.to_string(),
+            port: DEFAULT_WEB_PORT,
+            out: PathBuf::from(DEFAULT_WEB_OUT_DIR),
+            posts: PathBuf::from(DEFAULT_WEB_POSTS_DIR),
+            templates: PathBuf::from(DEFAULT_WEB_TEMPLATES_DIR),
+            assets: PathBuf::from(DEFAULT_WEB_ASSETS_DIR),
+            page_size: DEFAULT_WEB_PAGE_SIZE,
+            cache: PathBuf::from(DEFAULT_WEB_CACHE_DIR),
+        }
+    }
+}
+
+impl SneakyWebConfig {
+    /// The address the dev server binds to
+    pub fn addr(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
+}
+
+/// GitHub flavoured markdown extensions used when rendering articles, read from the
+/// `[markdown]` section
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SneakyMarkdownConfig {
+    pub tables: bool,
+    pub strikethrough: bool,
+    pub task_lists: bool,
+    /// Turn bare urls and emails into links
+    pub autolinks: bool,
+    /// Collect `[^note]` footnotes into a section at the end of the post
+    pub footnotes: bool,
+    /// Render `$...$` and `$$...$$` TeX math to MathML
+    pub math: bool,
+}
+
+impl Default for SneakyMarkdownConfig {
+    fn default() -> Self {
+        Self {
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            autolinks: true,
+            footnotes: true,
+            math: true,
+        }
+    }
+}
+
+/// The layout of the configuration file
+#[derive(Deserialize)]
+struct SneakyConfigFile {
+    #[serde(flatten)]
+    me: SneakyMeta,
+    #[serde(default)]
+    site: SneakySite,
+    #[serde(default)]
+    web: SneakyWebConfig,
+    #[serde(default)]
+    markdown: SneakyMarkdownConfig,
+}
+
 #[derive(Serialize, Clone, Deserialize)]
 pub struct SneakyBuildInfo {
     pub hash: String,
@@ -39,12 +171,39 @@ pub const DEFAULT_CONFIG_FILE: &str = "sc.toml";
 pub const DEFAULT_NAME: &str = "Zachary Corivdae";
 pub const DEFAULT_EMAIL: &str = "zach@sneakycrow.dev";
 pub const DEFAULT_TITLE: &str = "Artist & Engineer";
+pub const DEFAULT_BASE_URL: &str = "https://sneakycrow.dev";
+pub const DEFAULT_SITE_TITLE: &str = "sneaky crow";
+pub const DEFAULT_SITE_DESCRIPTION: &str = "brain juice from sneaky crow";
+pub const DEFAULT_WEB_HOST: &str = "0.0.0.0";
+pub const DEFAULT_WEB_PORT: u16 = 3000;
+pub const DEFAULT_WEB_OUT_DIR: &str = "build";
+pub const DEFAULT_WEB_POSTS_DIR: &str = "_posts";
+pub const DEFAULT_WEB_TEMPLATES_DIR: &str = "templates";
+pub const DEFAULT_WEB_ASSETS_DIR: &str = "assets";
+pub const DEFAULT_WEB_PAGE_SIZE: usize = 10;
+pub const DEFAULT_WEB_CACHE_DIR: &str = ".cache";
 
 impl SneakyContext {
+    /// Loads the context from a configuration file, using the defaults when it doesn't exist
+    pub fn load(file: &str) -> Result<Self, SneakyContextError> {
+        if std::path::Path::new(file).exists() {
+            Self::from_file(file)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
     /// Derives the context from a configuration file
     pub fn from_file(file: &str) -> Result<Self, SneakyContextError> {
-        let config = std::fs::read_to_string(file)?;
-        let meta: SneakyMeta = toml::from_str(&config)?;
+        let config = std::fs::read_to_string(file).map_err(|source| SneakyContextError::IO {
+            path: file.to_string(),
+            source,
+        })?;
+        let config: SneakyConfigFile =
+            toml::from_str(&config).map_err(|source| SneakyContextError::Toml {
+                path: file.to_string(),
+                source,
+            })?;
         let build_info = {
             if let Ok(env_build_hash) = std::env::var("BUILD_HASH") {
                 SneakyBuildInfo {
@@ -55,21 +214,11 @@ impl SneakyContext {
             }
         };
         Ok(Self {
-            me: meta,
+            me: config.me,
+            site: config.site,
+            web: config.web,
+            markdown: config.markdown,
             build_info,
         })
     }
 }
-
-impl Default for SneakyContext {
-    fn default() -> Self {
-        Self {
-            me: SneakyMeta {
-                name: DEFAULT_NAME.to_string(),
-                email: DEFAULT_EMAIL.to_string(),
-                title: DEFAULT_TITLE.to_string(),
-            },
-            build_info: SneakyBuildInfo::default(),
-        }
-    }
-}
@@ -0,0 +1,96 @@
+use crate::{Post, errors::WebError, render::Renderer};
+use chrono::{Datelike, NaiveDate};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Link to a year or month archive page
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct ArchiveLink {
+    pub name: String,
+    pub url: String,
+    pub count: usize,
+}
+
+/// Posts of a single month, keyed by month number
+type Month<'a> = (u32, Vec<&'a Post>);
+
+/// Posts grouped by year, then by month, newest first
+fn group(posts: &[Post]) -> Vec<(i32, Vec<Month<'_>>)> {
+    let mut years: BTreeMap<i32, BTreeMap<u32, Vec<&Post>>> = BTreeMap::new();
+    for post in posts {
+        years
+            .entry(post.dated.year())
+            .or_default()
+            .entry(post.dated.month())
+            .or_default()
+            .push(post);
+    }
+
+    years
+        .into_iter()
+        .rev()
+        .map(|(year, months)| (year, months.into_iter().rev().collect()))
+        .collect()
+}
+
+/// Name of a month, e.g. "October"
+fn month_name(month: u32) -> String {
+    NaiveDate::from_ymd_opt(2000, month, 1)
+        .map(|d| d.format("%B").to_string())
+        .unwrap_or_else(|| month.to_string())
+}
+
+/// Links to every year archive, used by the blog index
+pub(crate) fn years(posts: &[Post]) -> Vec<ArchiveLink> {
+    group(posts)
+        .into_iter()
+        .map(|(year, months)| ArchiveLink {
+            name: year.to_string(),
+            url: format!("/blog/{year}/"),
+            count: months.iter().map(|(_, posts)| posts.len()).sum(),
+        })
+        .collect()
+}
+
+/// Renders a page per year and per month that has posts
+pub(crate) fn render(renderer: &Renderer, posts: &[Post]) -> Result<(), WebError> {
+    for (year, months) in group(posts) {
+        let month_links: Vec<ArchiveLink> = months
+            .iter()
+            .map(|(month, posts)| ArchiveLink {
+                name: month_name(*month),
+                url: format!("/blog/{year}/{month:02}/"),
+                count: posts.len(),
+            })
+            .collect();
+
+        for (month, month_posts) in &months {
+            tracing::debug!("rendering archive page: {year}-{month:02}");
+            renderer.page(
+                "archive",
+                format!("blog/{year}/{month:02}"),
+                json!({
+                    "title": format!("{} {year}", month_name(*month)),
+                    "parent_url": format!("/blog/{year}/"),
+                    "posts": month_posts,
+                }),
+            )?;
+        }
+
+        tracing::debug!("rendering archive page: {year}");
+        let year_posts: Vec<&Post> = months.into_iter().flat_map(|(_, p)| p).collect();
+        renderer.page(
+            "archive",
+            format!("blog/{year}"),
+            json!({
+                "title": year.to_string(),
+                "parent_url": "/blog/",
+                "months": month_links,
+                "posts": year_posts,
+            }),
+        )?;
+    }
+
+    Ok(())
+}
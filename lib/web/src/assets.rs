@@ -0,0 +1,240 @@
+use crate::{copy_dir, create_dir, errors::WebError, images::hash, write_file};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+    path::Path,
+};
+
+/// Manifest of fingerprinted assets, in the build directory
+const MANIFEST_FILE: &str = "assets-manifest.json";
+
+/// Url static assets are served under
+const ASSETS_URL: &str = "/assets";
+
+/// Fingerprinted copies of the static assets, by their path in the assets directory
+///
+/// Top-level assets are published both as is and under a name with a hash of
+/// their content (`main.3f2a9c1e.css`), so pages can point at a copy that
+/// never goes stale in caches. Whatever their stylesheets `@import` or point
+/// at with `url()` is fingerprinted too, and the references rewritten to the
+/// hashed names
+#[derive(Default)]
+pub(crate) struct Manifest {
+    files: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Loads the manifest written by the last build, empty if there isn't one
+    pub fn load(out: &Path) -> Result<Self, WebError> {
+        let path = out.join(MANIFEST_FILE);
+        if !path.exists() {
+            tracing::warn!(
+                "no {MANIFEST_FILE} in the build directory, assets aren't fingerprinted"
+            );
+            return Ok(Self::default());
+        }
+        let json = std::fs::read(&path).map_err(WebError::io_at(&path))?;
+
+        Ok(Self {
+            files: serde_json::from_slice(&json)?,
+        })
+    }
+
+    /// Writes the manifest to the build directory, telling whether it changed
+    pub fn write(&self, out: &Path) -> Result<bool, WebError> {
+        let path = out.join(MANIFEST_FILE);
+        let json = serde_json::to_string_pretty(&self.files)?;
+        if std::fs::read_to_string(&path).is_ok_and(|old| old == json) {
+            return Ok(false);
+        }
+        write_file(&path, json)?;
+        Ok(true)
+    }
+
+    /// Publishes a generated asset along with its fingerprinted copy
+    pub fn publish(&mut self, relative: &str, contents: &[u8], dir: &Path) -> Result<(), WebError> {
+        let hashed = fingerprint(relative, contents);
+        for name in [relative, hashed.as_str()] {
+            write_asset(&dir.join(name), contents)?;
+        }
+        self.files.insert(relative.to_string(), hashed);
+        Ok(())
+    }
+
+    /// Publishes a directory of assets
+    ///
+    /// Every file is copied as is, the top-level ones and what they reference
+    /// also get a fingerprinted copy
+    pub fn publish_dir(&mut self, src: &Path, dir: &Path) -> Result<(), WebError> {
+        copy_dir(src, dir)?;
+        for entry in std::fs::read_dir(src).map_err(WebError::io_at(src))? {
+            let path = entry.map_err(WebError::io_at(src))?.path();
+            if let (true, Some(name)) = (path.is_file(), path.file_name()) {
+                self.publish_file(src, &name.to_string_lossy(), dir, &mut BTreeSet::new())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Publishes the fingerprinted copy of an asset, returning its name
+    ///
+    /// The files a stylesheet references are published first so their hashed
+    /// names end up in it, and its own hash changes along with theirs
+    fn publish_file(
+        &mut self,
+        src: &Path,
+        relative: &str,
+        dir: &Path,
+        pending: &mut BTreeSet<String>,
+    ) -> Result<String, WebError> {
+        if let Some(hashed) = self.files.get(relative) {
+            return Ok(hashed.clone());
+        }
+        let path = src.join(relative);
+        let mut contents = std::fs::read(&path).map_err(WebError::io_at(&path))?;
+
+        if relative.ends_with(".css") {
+            pending.insert(relative.to_string());
+            let css = String::from_utf8_lossy(&contents).into_owned();
+            let mut rewritten = String::with_capacity(css.len());
+            let mut last = 0;
+            for range in references(&css) {
+                let reference = &css[range.clone()];
+                let Some(target) = resolve(relative, reference) else {
+                    continue;
+                };
+                // Import cycles keep their plain names
+                if pending.contains(&target) || !src.join(&target).is_file() {
+                    continue;
+                }
+                let hashed = self.publish_file(src, &target, dir, pending)?;
+                rewritten.push_str(&css[last..range.start]);
+                rewritten.push_str(&rename(reference, &hashed));
+                last = range.end;
+            }
+            rewritten.push_str(&css[last..]);
+            pending.remove(relative);
+            contents = rewritten.into_bytes();
+        }
+
+        let hashed = fingerprint(relative, &contents);
+        write_asset(&dir.join(&hashed), &contents)?;
+        self.files.insert(relative.to_string(), hashed.clone());
+        Ok(hashed)
+    }
+
+    /// Url of an asset, fingerprinted when it's in the manifest
+    pub fn url(&self, relative: &str) -> String {
+        let relative = relative.trim_start_matches('/');
+        match self.files.get(relative) {
+            Some(hashed) => format!("{ASSETS_URL}/{hashed}"),
+            None => {
+                tracing::warn!("asset `{relative}` isn't in the manifest, linking it as is");
+                format!("{ASSETS_URL}/{relative}")
+            }
+        }
+    }
+}
+
+/// The `asset` helper, `{{asset "main.css"}}` gives the url of an asset
+impl HelperDef for Manifest {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let name = h
+            .param(0)
+            .and_then(|param| param.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("asset", 0))?;
+        out.write(&self.url(name))?;
+        Ok(())
+    }
+}
+
+/// Name of an asset with a hash of its content before the extension
+fn fingerprint(relative: &str, contents: &[u8]) -> String {
+    let hash = &hash(contents)[..8];
+    let (dir, name) = match relative.rsplit_once('/') {
+        Some((dir, name)) => (format!("{dir}/"), name),
+        None => (String::new(), relative),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{dir}{stem}.{hash}.{ext}"),
+        _ => format!("{dir}{name}.{hash}"),
+    }
+}
+
+/// Writes an asset, creating its directory if needed
+fn write_asset(path: &Path, contents: &[u8]) -> Result<(), WebError> {
+    if let Some(parent) = path.parent() {
+        create_dir(parent)?;
+    }
+    write_file(path, contents)
+}
+
+/// Byte ranges of the urls a stylesheet imports or points at with `url()`
+fn references(css: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for (start, keyword) in css
+        .match_indices("url(")
+        .chain(css.match_indices("@import"))
+    {
+        let rest = &css[start + keyword.len()..];
+        let trimmed = rest.trim_start();
+        let from = css.len() - trimmed.len();
+        let range = match trimmed.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                trimmed[1..].find(quote).map(|end| from + 1..from + 1 + end)
+            }
+            _ if keyword == "url(" => trimmed
+                .find(')')
+                .map(|end| from..from + trimmed[..end].trim_end().len()),
+            _ => None,
+        };
+        ranges.extend(range);
+    }
+    ranges.sort_by_key(|range| range.start);
+    ranges
+}
+
+/// Path in the assets directory a stylesheet reference points at
+///
+/// `None` for external urls, data urls and paths outside the assets
+fn resolve(stylesheet: &str, reference: &str) -> Option<String> {
+    let path = reference.split(['?', '#']).next()?;
+    if path.is_empty() || path.contains(':') || path.starts_with("//") {
+        return None;
+    }
+    let (base, path) = match path.strip_prefix(ASSETS_URL) {
+        Some(rest) if rest.starts_with('/') => ("", rest),
+        _ if path.starts_with('/') => return None,
+        _ => (stylesheet.rsplit_once('/').map_or("", |(dir, _)| dir), path),
+    };
+
+    let mut parts: Vec<&str> = base.split('/').filter(|part| !part.is_empty()).collect();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// A reference with its file name swapped for the fingerprinted one
+fn rename(reference: &str, hashed: &str) -> String {
+    let end = reference.find(['?', '#']).unwrap_or(reference.len());
+    let start = reference[..end].rfind('/').map_or(0, |slash| slash + 1);
+    let name = hashed.rsplit('/').next().unwrap_or(hashed);
+    format!("{}{name}{}", &reference[..start], &reference[end..])
+}
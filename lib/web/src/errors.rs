@@ -1,15 +1,60 @@
 use articles::errors::ArticleError;
-use handlebars::RenderError;
+use handlebars::{RenderError, TemplateError};
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum WebError {
     #[error("Failed loading articles: {0}")]
     ArticlesLoad(#[from] ArticleError),
+    #[error("Failed rendering article: {0}")]
+    ArticleRender(#[source] ArticleError),
     #[error("I/O Failure: {0}")]
     IO(#[from] std::io::Error),
+    #[error("I/O Failure at {}: {source}", path.display())]
+    PathIO {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Could not bind to {addr}: {source}")]
+    Bind {
+        addr: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Server error: {0}")]
+    Serve(#[source] std::io::Error),
     #[error("Handlebars render error: {0}")]
     Render(#[from] RenderError),
+    #[error("Handlebars template error: {0}")]
+    Template(#[from] TemplateError),
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("File watcher error: {0}")]
+    Watch(#[from] notify::Error),
+    #[error("{post}: image `{src}` not found at {}", path.display())]
+    MissingImage {
+        post: String,
+        src: String,
+        path: PathBuf,
+    },
+    #[error("Could not process image {}: {source}", path.display())]
+    Image {
+        path: PathBuf,
+        #[source]
+        source: image::ImageError,
+    },
     #[error("Context error: {0}")]
     Context(#[from] context::SneakyContextError),
 }
+
+impl WebError {
+    /// Creates a mapper attaching the path an I/O error happened at
+    pub fn io_at(path: &Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| Self::PathIO {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{Post, create_dir, errors::WebError, write_file};
+use context::{SneakyContext, SneakySite};
+use serde_json::json;
+use std::path::Path;
+
+/// File names of the generated feeds, relative to the listing they syndicate
+pub const RSS_FEED: &str = "feed.xml";
+pub const ATOM_FEED: &str = "atom.xml";
+pub const JSON_FEED: &str = "feed.json";
+
+/// Attributes of the content holding urls
+const URL_ATTRIBUTES: [&str; 4] = ["href", "src", "srcset", "poster"];
+
+/// A syndication feed for a listing of posts
+pub(crate) struct Feed<'a> {
+    /// Title of the feed
+    pub title: String,
+    /// Site-relative path of the listing the feed belongs to, e.g. `/blog`
+    pub path: String,
+    /// Posts in the feed, newest first
+    pub posts: Vec<&'a Post>,
+}
+
+impl<'a> Feed<'a> {
+    /// Creates a feed for a listing, sorting the posts newest first
+    pub fn new(title: String, path: String, posts: impl IntoIterator<Item = &'a Post>) -> Self {
+        let mut posts: Vec<&Post> = posts.into_iter().collect();
+        posts.sort_by_key(|p| std::cmp::Reverse(p.published));
+        Self { title, path, posts }
+    }
+
+    /// Writes the RSS, Atom and JSON feeds into the given directory
+    pub fn write(&self, dir: &Path, ctx: &SneakyContext) -> Result<(), WebError> {
+        create_dir(dir)?;
+        write_file(&dir.join(RSS_FEED), self.rss(ctx))?;
+        write_file(&dir.join(ATOM_FEED), self.atom(ctx))?;
+        write_file(&dir.join(JSON_FEED), self.json(ctx)?)?;
+        Ok(())
+    }
+
+    /// Absolute url of the feed file with the given name
+    fn feed_url(&self, ctx: &SneakyContext, file: &str) -> String {
+        ctx.site.url(&format!("{}/{file}", self.path))
+    }
+
+    /// Renders the feed as RSS 2.0
+    fn rss(&self, ctx: &SneakyContext) -> String {
+        let mut xml = String::new();
+        xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        xml.push('\n');
+        xml.push_str(r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:content="http://purl.org/rss/1.0/modules/content/">"#);
+        xml.push_str("<channel>");
+        xml.push_str(&element("title", &self.title));
+        xml.push_str(&element("link", &ctx.site.url(&self.path)));
+        xml.push_str(&element("description", &ctx.site.description));
+        xml.push_str(&format!(
+            r#"<atom:link href="{}" rel="self" type="application/rss+xml"/>"#,
+            escape(&self.feed_url(ctx, RSS_FEED))
+        ));
+        if let Some(latest) = self.posts.first() {
+            xml.push_str(&element("lastBuildDate", &latest.published.to_rfc2822()));
+        }
+
+        for post in &self.posts {
+            let url = ctx.site.url(&post.url);
+            xml.push_str("<item>");
+            xml.push_str(&element("title", &post.title));
+            xml.push_str(&element("link", &url));
+            xml.push_str(&format!(
+                r#"<guid isPermaLink="true">{}</guid>"#,
+                escape(&url)
+            ));
+            xml.push_str(&element("pubDate", &post.published.to_rfc2822()));
+            xml.push_str(&element(
+                "author",
+                &format!("{} ({})", ctx.me.email, post.author),
+            ));
+            for term in post.category.iter().chain(post.tags.iter()) {
+                xml.push_str(&element("category", &term.name));
+            }
+            if !post.excerpt.is_empty() {
+                xml.push_str(&element("description", &post.excerpt));
+            }
+            xml.push_str(&format!(
+                "<content:encoded><![CDATA[{}]]></content:encoded>",
+                content(post, ctx).replace("]]>", "]]]]><![CDATA[>")
+            ));
+            xml.push_str("</item>");
+        }
+
+        xml.push_str("</channel></rss>\n");
+        xml
+    }
+
+    /// Renders the feed as Atom
+    fn atom(&self, ctx: &SneakyContext) -> String {
+        let updated = self
+            .posts
+            .iter()
+            .map(|p| p.modified.unwrap_or(p.published))
+            .max()
+            .map(|u| u.to_rfc3339())
+            .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
+
+        let mut xml = String::new();
+        xml.push_str(r#"<?xml version="1.0" encoding="utf-8"?>"#);
+        xml.push('\n');
+        xml.push_str(r#"<feed xmlns="http://www.w3.org/2005/Atom">"#);
+        xml.push_str(&element("title", &self.title));
+        xml.push_str(&element("subtitle", &ctx.site.description));
+        xml.push_str(&element("id", &ctx.site.url(&self.path)));
+        xml.push_str(&format!(
+            r#"<link href="{}" rel="alternate" type="text/html"/>"#,
+            escape(&ctx.site.url(&self.path))
+        ));
+        xml.push_str(&format!(
+            r#"<link href="{}" rel="self" type="application/atom+xml"/>"#,
+            escape(&self.feed_url(ctx, ATOM_FEED))
+        ));
+        xml.push_str(&element("updated", &updated));
+        xml.push_str(&format!(
+            "<author>{}{}</author>",
+            element("name", &ctx.me.name),
+            element("email", &ctx.me.email)
+        ));
+
+        for post in &self.posts {
+            let url = ctx.site.url(&post.url);
+            xml.push_str("<entry>");
+            xml.push_str(&element("title", &post.title));
+            xml.push_str(&element("id", &url));
+            xml.push_str(&format!(
+                r#"<link href="{}" rel="alternate" type="text/html"/>"#,
+                escape(&url)
+            ));
+            xml.push_str(&element("published", &post.published.to_rfc3339()));
+            xml.push_str(&element(
+                "updated",
+                &post.modified.unwrap_or(post.published).to_rfc3339(),
+            ));
+            xml.push_str(&format!(
+                "<author>{}</author>",
+                element("name", &post.author)
+            ));
+            for term in post.category.iter().chain(post.tags.iter()) {
+                xml.push_str(&format!(r#"<category term="{}"/>"#, escape(&term.name)));
+            }
+            if !post.excerpt.is_empty() {
+                xml.push_str(&element("summary", &post.excerpt));
+            }
+            xml.push_str(&format!(
+                r#"<content type="html">{}</content>"#,
+                escape(&content(post, ctx))
+            ));
+            xml.push_str("</entry>");
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    /// Renders the feed as JSON Feed 1.1
+    fn json(&self, ctx: &SneakyContext) -> Result<String, WebError> {
+        let items: Vec<serde_json::Value> = self
+            .posts
+            .iter()
+            .map(|post| {
+                let url = ctx.site.url(&post.url);
+                let tags: Vec<&String> = post
+                    .category
+                    .iter()
+                    .chain(post.tags.iter())
+                    .map(|term| &term.name)
+                    .collect();
+                let mut item = json!({
+                    "id": &url,
+                    "url": &url,
+                    "title": &post.title,
+                    "content_html": content(post, ctx),
+                    "summary": Some(&post.excerpt).filter(|e| !e.is_empty()),
+                    "image": &post.cover_image,
+                    "date_published": post.published.to_rfc3339(),
+                    "date_modified": post.modified.map(|m| m.to_rfc3339()),
+                    "authors": [{ "name": &post.author }],
+                    "tags": tags,
+                });
+
+                // optional members are omitted rather than null
+                if let Some(item) = item.as_object_mut() {
+                    item.retain(|_, v| !v.is_null());
+                }
+                item
+            })
+            .collect();
+
+        let feed = json!({
+            "version": "https://jsonfeed.org/version/1.1",
+            "title": &self.title,
+            "description": &ctx.site.description,
+            "home_page_url": ctx.site.url(&self.path),
+            "feed_url": self.feed_url(ctx, JSON_FEED),
+            "authors": [{ "name": &ctx.me.name, "url": ctx.site.url("/") }],
+            "items": items,
+        });
+
+        Ok(serde_json::to_string_pretty(&feed)?)
+    }
+}
+
+/// The content of a post as feeds carry it, with its urls made absolute
+///
+/// Feed readers show the content away from the site, where site-relative
+/// urls and bare `#fragment`s lead nowhere
+fn content(post: &Post, ctx: &SneakyContext) -> String {
+    let page = ctx.site.url(&post.url);
+    let mut html = String::with_capacity(post.content.len());
+    let mut rest = post.content.as_str();
+
+    while let Some(eq) = rest.find('=') {
+        let (before, after) = rest.split_at(eq + 1);
+        html.push_str(before);
+        rest = after;
+
+        // only quoted values of the url attributes of a tag
+        let name = before[..eq]
+            .rsplit(|c: char| c.is_whitespace())
+            .next()
+            .unwrap_or_default();
+        let is_attribute = URL_ATTRIBUTES.contains(&name.to_ascii_lowercase().as_str())
+            && before[..eq - name.len()].ends_with(char::is_whitespace);
+        let Some(quote) = rest.chars().next().filter(|c| matches!(c, '"' | '\'')) else {
+            continue;
+        };
+        let Some(end) = rest[1..].find(quote).filter(|_| is_attribute) else {
+            continue;
+        };
+
+        let value = &rest[1..end + 1];
+        let value = if name.eq_ignore_ascii_case("srcset") {
+            value
+                .split(',')
+                .map(|candidate| {
+                    let candidate = candidate.trim();
+                    match candidate.split_once(' ') {
+                        Some((url, descriptor)) => {
+                            format!("{} {descriptor}", absolute(url, &ctx.site, &page))
+                        }
+                        None => absolute(candidate, &ctx.site, &page),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            absolute(value, &ctx.site, &page)
+        };
+        html.push(quote);
+        html.push_str(&value);
+        html.push(quote);
+        rest = &rest[end + 2..];
+    }
+
+    html.push_str(rest);
+    html
+}
+
+/// A url of the content that works anywhere, fragments are resolved against the page
+fn absolute(url: &str, site: &SneakySite, page: &str) -> String {
+    if url.starts_with('#') {
+        format!("{page}{url}")
+    } else if url.starts_with('/') && !url.starts_with("//") {
+        site.url(url)
+    } else {
+        url.to_string()
+    }
+}
+
+/// Renders a simple xml element with escaped text content
+fn element(name: &str, text: &str) -> String {
+    format!("<{name}>{}</{name}>", escape(text))
+}
+
+/// Escapes text for use in xml content and attributes
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
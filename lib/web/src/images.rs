@@ -0,0 +1,543 @@
+use crate::{create_dir, errors::WebError, post_url};
+use articles::{
+    Article,
+    render::{self as markdown, Attributes, Block, Image, Inline, Link, escape},
+};
+use context::SneakyWebConfig;
+use image::{DynamicImage, ImageFormat, imageops::FilterType};
+use sha2::{Digest, Sha256};
+use std::path::{Component, Path, PathBuf};
+
+/// Widths responsive variants are made in, when the original is wider
+const WIDTHS: [u32; 3] = [480, 960, 1440];
+
+/// Width images take up on the page, at most the width of the content
+const SIZES: &str = "(max-width: 1000px) 100vw, 1000px";
+
+/// A local image, where it's published and at what url
+struct Source {
+    path: PathBuf,
+    out: PathBuf,
+    url: String,
+    format: ImageFormat,
+}
+
+/// A resized copy of an image
+struct Variant {
+    source: PathBuf,
+    width: u32,
+    format: ImageFormat,
+    out: PathBuf,
+}
+
+/// Makes responsive variants of the local images in a post and points the post at them
+///
+/// Relative images come from a folder next to the post, named after its slug
+/// or its file (`_posts/<slug>/`), which is published along with the post.
+/// `/assets/` images come from the assets directory. Raster images are
+/// resized to a few widths and to WebP, with the results cached by content
+/// between builds
+pub(crate) struct Images<'a> {
+    config: &'a SneakyWebConfig,
+    /// Folder of the post's own files, if it has one
+    folder: Option<PathBuf>,
+    /// Page url of the post, its own files are published under it
+    url: String,
+    /// The post, as named in errors
+    post: String,
+    variants: Vec<Variant>,
+    /// First error hit while rewriting, transforms can't return them
+    error: Option<WebError>,
+}
+
+impl<'a> Images<'a> {
+    pub fn new(config: &'a SneakyWebConfig, article: &Article) -> Self {
+        let stem = article
+            .source
+            .as_ref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned());
+        let folder = [Some(article.slug()), stem]
+            .into_iter()
+            .flatten()
+            .map(|name| config.posts.join(name))
+            .find(|folder| folder.is_dir());
+
+        Images {
+            config,
+            folder,
+            url: post_url(article),
+            post: article
+                .source
+                .as_ref()
+                .map_or_else(|| article.title.clone(), |path| path.display().to_string()),
+            variants: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Publishes the post's folder and writes the variants of its images
+    pub fn write(self) -> Result<(), WebError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+
+        if let Some(folder) = &self.folder {
+            let out = self.config.out.join(self.url.trim_start_matches('/'));
+            create_dir(&out)?;
+            crate::copy_dir(folder, &out)?;
+        }
+
+        // variants of an image are planned together, it's decoded once if anything isn't cached
+        let cache = self.config.cache.join("images");
+        for variants in self.variants.chunk_by(|a, b| a.source == b.source) {
+            let source = &variants[0].source;
+            let bytes = std::fs::read(source).map_err(WebError::io_at(source))?;
+            let hash = hash(&bytes);
+            let mut image = None;
+
+            for variant in variants {
+                let cached = cache.join(format!(
+                    "{hash}-{}w.{}",
+                    variant.width,
+                    variant.format.extensions_str()[0]
+                ));
+                if !cached.exists() {
+                    let image = match &mut image {
+                        Some(image) => image,
+                        None => image.insert(image::load_from_memory(&bytes).map_err(|e| {
+                            WebError::Image {
+                                path: source.clone(),
+                                source: e,
+                            }
+                        })?),
+                    };
+                    tracing::debug!("resizing {} to {}px", source.display(), variant.width);
+                    let resized = image.resize(variant.width, u32::MAX, FilterType::CatmullRom);
+                    let resized = match variant.format {
+                        ImageFormat::Jpeg => DynamicImage::ImageRgb8(resized.to_rgb8()),
+                        _ => resized,
+                    };
+                    create_dir(&cache)?;
+                    resized
+                        .save_with_format(&cached, variant.format)
+                        .map_err(|e| WebError::Image {
+                            path: cached.clone(),
+                            source: e,
+                        })?;
+                }
+
+                if let Some(dir) = variant.out.parent() {
+                    create_dir(dir)?;
+                }
+                std::fs::copy(&cached, &variant.out).map_err(WebError::io_at(&cached))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The local file a destination points at, where it's published and at what url
+    fn locate(&self, destination: &str) -> Option<(PathBuf, PathBuf, String)> {
+        if destination.contains([':', '?', '#']) || destination.starts_with("//") {
+            return None;
+        }
+
+        let (dir, out, url, relative) = match destination.strip_prefix("/assets/") {
+            Some(relative) => (
+                self.config.assets.clone(),
+                self.config.out.join("assets"),
+                "/assets".to_string(),
+                relative,
+            ),
+            None if !destination.starts_with('/') => (
+                self.folder.clone()?,
+                self.config.out.join(self.url.trim_start_matches('/')),
+                self.url.clone(),
+                destination,
+            ),
+            None => return None,
+        };
+
+        // only paths inside the folder, without any `..`
+        let mut parts = Vec::new();
+        for component in Path::new(relative).components() {
+            match component {
+                Component::Normal(part) => parts.push(part.to_str()?),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+
+        let relative = parts.join("/");
+        Some((
+            dir.join(&relative),
+            out.join(&relative),
+            format!("{url}/{relative}"),
+        ))
+    }
+
+    /// The local raster image an image destination points at
+    fn source(&self, destination: &str) -> Option<Source> {
+        let format = match ImageFormat::from_path(destination).ok()? {
+            format @ (ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP) => format,
+            _ => return None,
+        };
+        let (path, out, url) = self.locate(destination)?;
+
+        Some(Source {
+            path,
+            out,
+            url,
+            format,
+        })
+    }
+
+    /// Points a relative destination at the post's published folder
+    fn publish(&self, destination: &mut String) {
+        if destination.starts_with('/') {
+            return;
+        }
+        if let Some((path, _, url)) = self.locate(destination)
+            && path.exists()
+        {
+            *destination = url;
+        }
+    }
+
+    /// A `<picture>` of the variants of an image, if it's a local raster image
+    fn picture(&mut self, image: &Image) -> Result<Option<String>, WebError> {
+        let Some(source) = self.source(&image.destination) else {
+            return Ok(None);
+        };
+        if !source.path.is_file() {
+            return Err(WebError::MissingImage {
+                post: self.post.clone(),
+                src: image.destination.clone(),
+                path: source.path,
+            });
+        }
+        let (width, height) =
+            image::image_dimensions(&source.path).map_err(|e| WebError::Image {
+                path: source.path.clone(),
+                source: e,
+            })?;
+
+        // narrower copies in the original format, and WebP at every width
+        let widths: Vec<u32> = WIDTHS.into_iter().filter(|w| *w < width).collect();
+        let mut srcset = Vec::new();
+        let mut webp = Vec::new();
+        for w in widths.iter().copied().chain([width]) {
+            if w < width {
+                srcset.push(format!("{} {w}w", self.variant(&source, w, source.format)));
+            }
+            if source.format != ImageFormat::WebP || w < width {
+                webp.push(format!(
+                    "{} {w}w",
+                    self.variant(&source, w, ImageFormat::WebP)
+                ));
+            }
+        }
+        srcset.push(format!("{} {width}w", source.url));
+
+        let mut html = String::from("<picture>");
+        if !webp.is_empty() {
+            html.push_str(&format!(
+                "<source type=\"image/webp\" srcset=\"{}\" sizes=\"{SIZES}\" />",
+                escape(&webp.join(", "))
+            ));
+        }
+        html.push_str(&format!(
+            "<img src=\"{}\" srcset=\"{}\" sizes=\"{SIZES}\"",
+            escape(&source.url),
+            escape(&srcset.join(", ")),
+        ));
+        // a size given with the image wins over the real one
+        let sized = image
+            .user_data
+            .other
+            .iter()
+            .any(|(name, _)| name == "width" || name == "height");
+        if !sized {
+            html.push_str(&format!(" width=\"{width}\" height=\"{height}\""));
+        }
+        html.push_str(&format!(" alt=\"{}\"", escape(&image.alt)));
+        if let Some(title) = &image.title {
+            html.push_str(&format!(" title=\"{}\"", escape(title)));
+        }
+        html.push_str(&image.user_data.to_html());
+        html.push_str(" loading=\"lazy\" decoding=\"async\" /></picture>");
+
+        Ok(Some(html))
+    }
+
+    /// Rewrites the `<img>` tags in raw HTML the way markdown images are
+    fn rewrite_html(&mut self, html: &mut String) {
+        let lower = html.to_ascii_lowercase();
+        let mut rewritten = String::with_capacity(html.len());
+        let mut last = 0;
+        for (start, _) in lower.match_indices("<img") {
+            let Some((len, attributes)) = img_tag(&html[start..]).filter(|_| start >= last) else {
+                continue;
+            };
+            match self.rewrite_tag(&attributes) {
+                Ok(Some(tag)) => {
+                    rewritten.push_str(&html[last..start]);
+                    rewritten.push_str(&tag);
+                    last = start + len;
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    self.error.get_or_insert(error);
+                }
+            }
+        }
+        if last > 0 {
+            rewritten.push_str(&html[last..]);
+            *html = rewritten;
+        }
+    }
+
+    /// What an `<img>` tag in raw HTML is replaced with, if anything
+    ///
+    /// Tags with their own `srcset` only get their `src` published
+    fn rewrite_tag(&mut self, attributes: &[(String, String)]) -> Result<Option<String>, WebError> {
+        let get = |name: &str| {
+            attributes
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let Some(src) = get("src") else {
+            return Ok(None);
+        };
+
+        if get("srcset").is_none() {
+            let mut image = Image {
+                destination: src.to_string(),
+                title: get("title").map(str::to_string),
+                alt: get("alt").unwrap_or_default().to_string(),
+                user_data: Attributes::default(),
+            };
+            for (name, value) in attributes {
+                match name.as_str() {
+                    "src" | "alt" | "title" | "sizes" | "loading" | "decoding" => {}
+                    "id" => image.user_data.id = Some(value.clone()),
+                    "class" => image
+                        .user_data
+                        .classes
+                        .extend(value.split_whitespace().map(str::to_string)),
+                    _ => image.user_data.other.push((name.clone(), value.clone())),
+                }
+            }
+            if let Some(html) = self.picture(&image)? {
+                return Ok(Some(html));
+            }
+        }
+
+        let mut changed = false;
+        let mut html = String::from("<img");
+        for (name, value) in attributes {
+            let published = match name.as_str() {
+                "src" => {
+                    let mut src = value.clone();
+                    self.publish(&mut src);
+                    src
+                }
+                "srcset" => value
+                    .split(',')
+                    .map(|candidate| {
+                        let candidate = candidate.trim();
+                        let (url, descriptor) = candidate
+                            .split_once(char::is_whitespace)
+                            .unwrap_or((candidate, ""));
+                        let mut url = url.to_string();
+                        self.publish(&mut url);
+                        format!("{url} {descriptor}").trim_end().to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => value.clone(),
+            };
+            changed |= published != *value;
+            html.push_str(&format!(" {name}=\"{}\"", escape(&published)));
+        }
+        html.push_str(" />");
+        Ok(changed.then_some(html))
+    }
+
+    /// Plans a variant of an image, giving its url
+    fn variant(&mut self, source: &Source, width: u32, format: ImageFormat) -> String {
+        let name = format!(
+            "{}-{width}w.{}",
+            source
+                .out
+                .file_stem()
+                .map(|stem| stem.to_string_lossy())
+                .unwrap_or_default(),
+            format.extensions_str()[0]
+        );
+        let out = source.out.with_file_name(&name);
+        if !self.variants.iter().any(|v| v.out == out) {
+            self.variants.push(Variant {
+                source: source.path.clone(),
+                width,
+                format,
+                out,
+            });
+        }
+
+        match source.url.rsplit_once('/') {
+            Some((dir, _)) => format!("{dir}/{name}"),
+            None => name,
+        }
+    }
+}
+
+impl markdown::Transform for Images<'_> {
+    fn block(&mut self, block: &mut Block) {
+        if let Block::HtmlBlock { content, .. } = block {
+            self.rewrite_html(content);
+        }
+    }
+
+    fn inline(&mut self, inline: &mut Inline) {
+        let image = match inline {
+            Inline::Image(image) => image,
+            Inline::Html { content, .. } => return self.rewrite_html(content),
+            _ => return,
+        };
+        match self.picture(image) {
+            Ok(Some(html)) => {
+                *inline = Inline::Html {
+                    content: html,
+                    user_data: Default::default(),
+                }
+            }
+            Ok(None) => self.publish(&mut image.destination),
+            Err(error) => {
+                self.error.get_or_insert(error);
+            }
+        }
+    }
+
+    fn link(&mut self, link: &mut Link) {
+        self.publish(&mut link.destination);
+    }
+}
+
+/// Length and attributes of the `<img>` tag starting some HTML
+///
+/// Attribute names are lowercased and the common entities in values decoded
+fn img_tag(html: &str) -> Option<(usize, Vec<(String, String)>)> {
+    let bytes = html.as_bytes();
+    let is_space = |i: usize| bytes.get(i).is_some_and(u8::is_ascii_whitespace);
+    if !html.get(..4)?.eq_ignore_ascii_case("<img")
+        || !(is_space(4) || html[4..].starts_with(['/', '>']))
+    {
+        return None;
+    }
+
+    let mut attributes = Vec::new();
+    let mut i = 4;
+    loop {
+        while is_space(i) || bytes.get(i) == Some(&b'/') {
+            i += 1;
+        }
+        if *bytes.get(i)? == b'>' {
+            return Some((i + 1, attributes));
+        }
+
+        let name_len = html[i..].find(|c: char| c.is_ascii_whitespace() || "=/>".contains(c))?;
+        if name_len == 0 {
+            return None;
+        }
+        let name = html[i..i + name_len].to_ascii_lowercase();
+        i += name_len;
+        while is_space(i) {
+            i += 1;
+        }
+
+        let mut value = String::new();
+        if bytes.get(i) == Some(&b'=') {
+            i += 1;
+            while is_space(i) {
+                i += 1;
+            }
+            // quoted values skip their closing quote
+            let (start, end, next) = match *bytes.get(i)? {
+                quote @ (b'"' | b'\'') => {
+                    let end = i + 1 + html[i + 1..].find(quote as char)?;
+                    (i + 1, end, end + 1)
+                }
+                _ => {
+                    let end = html[i..]
+                        .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                        .map_or(html.len(), |len| i + len);
+                    (i, end, end)
+                }
+            };
+            value = unescape(&html[start..end]);
+            i = next;
+        }
+        attributes.push((name, value));
+    }
+}
+
+/// Decodes the entities [`escape`] writes, along with `&#39;`
+fn unescape(text: &str) -> String {
+    text.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Short hex digest of some content
+pub(crate) fn hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .take(8)
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Images;
+    use articles::{Article, render};
+    use context::SneakyWebConfig;
+
+    #[test]
+    fn rewrites_img_tags_in_raw_html() {
+        let posts = std::env::temp_dir().join(format!("sc-images-{}", std::process::id()));
+        std::fs::create_dir_all(posts.join("post")).unwrap();
+        image::RgbImage::new(4, 3)
+            .save(posts.join("post/diagram.png"))
+            .unwrap();
+        std::fs::write(posts.join("post/chart.svg"), "<svg/>").unwrap();
+        let config = SneakyWebConfig {
+            posts: posts.clone(),
+            ..Default::default()
+        };
+        let article =
+            Article::try_from("---\ntitle: Post\ndate: 2024-01-01\n---\n".to_string()).unwrap();
+
+        let mut images = Images::new(&config, &article);
+        let html = render::render(
+            "<img src=\"diagram.png\" alt=\"A diagram\">\n\nSee <img src='chart.svg'>.\n",
+            &Default::default(),
+            &mut [&mut images],
+        );
+        std::fs::remove_dir_all(&posts).unwrap();
+
+        assert_eq!(
+            html.unwrap(),
+            "<picture><source type=\"image/webp\" srcset=\"/blog/post/diagram-4w.webp 4w\" \
+             sizes=\"(max-width: 1000px) 100vw, 1000px\" /><img src=\"/blog/post/diagram.png\" \
+             srcset=\"/blog/post/diagram.png 4w\" sizes=\"(max-width: 1000px) 100vw, 1000px\" \
+             width=\"4\" height=\"3\" alt=\"A diagram\" loading=\"lazy\" decoding=\"async\" /></picture>\n\
+             <p>See <img src=\"/blog/post/chart.svg\" />.</p>"
+        );
+    }
+}
@@ -1,81 +1,269 @@
 // TODO: Parse articles as static html
 // TODO: Serve articles from /blog
+mod archive;
+mod assets;
 pub mod errors;
-
-use articles::Article;
-use axum::{Router, extract::FromRef};
-use context::{DEFAULT_CONFIG_FILE, SneakyContext};
+mod feeds;
+mod images;
+mod live_reload;
+mod pagination;
+mod related;
+mod render;
+mod series;
+mod shortcodes;
+mod taxonomy;
+
+use articles::{
+    Article, Series, highlight, reading,
+    render::{self as markdown, TocEntry},
+};
+use assets::Manifest;
+use axum::{Router, extract::FromRef, routing::get};
+use chrono::DateTime;
+use chrono_tz::Tz;
+use context::{SneakyContext, SneakyWebConfig};
 use errors::WebError;
-use handlebars::Handlebars;
+use images::Images;
+use render::Renderer;
 use serde::Serialize;
 use serde_json::json;
-use std::path::PathBuf;
+use series::SeriesNav;
+use shortcodes::ShortcodeTemplates;
+use std::{path::Path, sync::Arc};
+use taxonomy::{Taxonomy, Term};
 use tower_http::services::ServeDir;
 
-const SOURCE_ARTICLES_DIR: &str = "_posts/";
-const BUILD_DIR: &str = "build";
-
 #[derive(Serialize)]
 struct Post {
     pub title: String,
     pub author: String,
     pub date: String,
     pub content: String,
+    /// Headings of the post, empty when it opts out
+    pub toc: Vec<TocEntry>,
     pub url: String,
-    pub filename: String,
+    pub slug: String,
+    pub updated: Option<String>,
+    pub category: Option<Term>,
+    pub tags: Vec<Term>,
+    pub summary: Option<String>,
+    /// Plain text introducing the post, its summary when it has one
+    pub excerpt: String,
+    pub word_count: usize,
+    pub reading_time_minutes: usize,
+    pub description: Option<String>,
+    pub cover_image: Option<String>,
+    pub draft: bool,
+    pub series: Option<SeriesNav>,
+    /// Posts that link to this one
+    pub backlinks: Vec<PostLink>,
+    /// Posts sharing its series, category or tags, most related first
+    pub related: Vec<PostLink>,
+    /// Destinations of the links in the content
+    #[serde(skip)]
+    pub links: Vec<String>,
+    /// The article date, posts are sorted, shown and archived by it
+    #[serde(skip)]
+    pub dated: DateTime<Tz>,
+    #[serde(skip)]
+    pub published: DateTime<Tz>,
+    #[serde(skip)]
+    pub modified: Option<DateTime<Tz>>,
 }
 
-impl From<Article> for Post {
-    fn from(article: Article) -> Self {
-        let content = article.clone().render_html();
-
-        Post {
-            url: format!("/blog/{}", article.filename()),
-            filename: article.filename(),
+impl Post {
+    /// Renders an article into a post, without its series navigation or links to other posts
+    fn new(
+        article: Article,
+        options: &markdown::Options,
+        images: &mut Images,
+    ) -> Result<Self, WebError> {
+        let mut links = related::Links::default();
+        let rendered = article
+            .render(options, &mut [&mut links, images])
+            .map_err(WebError::ArticleRender)?;
+        let published = article.published_at();
+
+        Ok(Post {
+            url: post_url(&article),
+            slug: article.slug(),
             title: article.title,
             author: article.author,
             date: article.date.format("%B %d, %Y").to_string(),
-            content: content,
+            content: rendered.html,
+            toc: rendered.toc,
+            updated: article.updated.map(|u| u.format("%B %d, %Y").to_string()),
+            category: article
+                .category
+                .map(|c| Term::new(Taxonomy::Categories, &c)),
+            tags: article
+                .tags
+                .iter()
+                .map(|t| Term::new(Taxonomy::Tags, t))
+                .collect(),
+            excerpt: rendered.excerpt,
+            summary: article.summary,
+            word_count: rendered.word_count,
+            reading_time_minutes: reading::reading_time_minutes(rendered.word_count),
+            description: article.description,
+            cover_image: article.cover_image,
+            draft: article.draft,
+            series: None,
+            backlinks: Vec::new(),
+            related: Vec::new(),
+            links: links.destinations,
+            dated: article.date,
+            published,
+            modified: article.updated,
+        })
+    }
+}
+
+/// Site-relative url of an article's page
+pub(crate) fn post_url(article: &Article) -> String {
+    format!("/blog/{}", article.slug())
+}
+
+/// Link to another post
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct PostLink {
+    pub title: String,
+    pub url: String,
+}
+
+impl From<&Article> for PostLink {
+    fn from(article: &Article) -> Self {
+        PostLink {
+            title: article.title.clone(),
+            url: post_url(article),
+        }
+    }
+}
+
+impl From<&Post> for PostLink {
+    fn from(post: &Post) -> Self {
+        PostLink {
+            title: post.title.clone(),
+            url: post.url.clone(),
         }
     }
 }
 
+/// Options controlling a build
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildOptions {
+    /// Include articles marked as `draft: true`
+    pub drafts: bool,
+    /// Include articles scheduled for a future date
+    pub future: bool,
+    /// Inject the live reload client into rendered pages, only used by `serve`
+    pub live_reload: bool,
+}
+
+impl BuildOptions {
+    /// Whether the article should be published with these options
+    pub fn includes(&self, article: &Article) -> bool {
+        if article.draft && !self.drafts {
+            return false;
+        }
+
+        if article.is_scheduled(chrono::Utc::now()) && !self.future {
+            return false;
+        }
+
+        true
+    }
+}
+
 #[derive(Clone, FromRef)]
 pub struct AppState {
     articles: Vec<Article>,
     context: SneakyContext,
+    options: BuildOptions,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            articles: Article::from_dir(PathBuf::from(SOURCE_ARTICLES_DIR)).unwrap_or_default(),
-            context: SneakyContext::from_file(DEFAULT_CONFIG_FILE).unwrap_or_default(),
-        }
+impl AppState {
+    /// Loads the state, keeping only the articles allowed by the options
+    ///
+    /// Directories are taken from the `web` configuration of the context
+    pub fn new(context: SneakyContext, options: BuildOptions) -> Result<Self, WebError> {
+        let articles = Article::from_dir(context.web.posts.clone())?
+            .into_iter()
+            .filter(|article| {
+                let included = options.includes(article);
+                if !included {
+                    tracing::debug!("skipping unpublished article: {}", article.title);
+                }
+                included
+            })
+            .collect();
+
+        Ok(Self {
+            articles,
+            context,
+            options,
+        })
+    }
+
+    /// The web configuration the state was loaded with
+    pub fn config(&self) -> &SneakyWebConfig {
+        &self.context.web
+    }
+
+    /// Markdown extensions, shortcodes and cross-link targets to render articles with
+    fn markdown_options(&self) -> Result<markdown::Options, WebError> {
+        let config = &self.context.markdown;
+        let shortcodes = ShortcodeTemplates::load(&self.config().templates)?;
+        Ok(markdown::Options {
+            tables: config.tables,
+            strikethrough: config.strikethrough,
+            task_lists: config.task_lists,
+            autolinks: config.autolinks,
+            footnotes: config.footnotes,
+            math: config.math,
+            shortcodes: Some(Arc::new(shortcodes)),
+            links: Some(Arc::new(markdown::CrossLinks::new(
+                &self.articles,
+                post_url,
+            ))),
+        })
     }
 }
 
-/// Serve the website
-pub async fn serve() -> Result<(), WebError> {
-    let state = AppState::default();
+/// Serve the website, rebuilding and reloading open pages when sources change
+pub async fn serve(context: SneakyContext, options: BuildOptions) -> Result<(), WebError> {
+    let options = BuildOptions {
+        live_reload: true,
+        ..options
+    };
+    let state = AppState::new(context.clone(), options)?;
 
     // build the static parts of the site
     build(&state)?;
 
+    // watch the sources for changes
+    let (reload, _) = tokio::sync::broadcast::channel(16);
+    let _watcher = live_reload::watch(context.clone(), options, reload.clone())?;
+
     // build the router
-    let router = Router::new().fallback_service(ServeDir::new(format!("{BUILD_DIR}")));
+    let router = Router::new()
+        .route(live_reload::LIVE_RELOAD_PATH, get(live_reload::events))
+        .fallback_service(ServeDir::new(&context.web.out))
+        .with_state(reload);
 
     // run the router
-    let port = 3000;
-    let host = format!("0.0.0.0:{port}");
+    let host = context.web.addr();
     let listener = tokio::net::TcpListener::bind(&host)
         .await
-        .expect("Could not bind the listener");
+        .map_err(|source| WebError::Bind {
+            addr: host.clone(),
+            source,
+        })?;
 
     tracing::info!("Listening on http://{}", host);
     axum::serve(listener, router)
         .await
-        .expect("Could not serve the listener");
+        .map_err(WebError::Serve)?;
 
     Ok(())
 }
@@ -84,144 +272,175 @@ pub async fn serve() -> Result<(), WebError> {
 fn prerender(state: &AppState) -> Result<(), WebError> {
     // load the articles
     tracing::debug!("loading articles");
-    let posts: Vec<Post> = state.articles.iter().map(|a| a.to_owned().into()).collect();
+    let series = Series::group(&state.articles);
+    let options = state.markdown_options()?;
+    let mut posts = state
+        .articles
+        .iter()
+        .map(|a| {
+            let mut images = Images::new(state.config(), a);
+            let post = Post::new(a.to_owned(), &options, &mut images)?;
+            images.write()?;
+            Ok(Post {
+                series: series::nav(&series, a),
+                ..post
+            })
+        })
+        .collect::<Result<Vec<_>, WebError>>()?;
+    related::link(&mut posts, &state.context.site.base_url);
 
     // initialize template engine
-    tracing::debug!("initializing template engine");
-    let mut hbs = Handlebars::new();
-    let _ = hbs.register_template_file("base", "./templates/base.hbs");
-    let _ = hbs.register_template_file("index", "./templates/index.hbs");
-    let _ = hbs.register_template_file("blog_index", "./templates/blog.hbs");
-    let _ = hbs.register_template_file("post", "./templates/post.hbs");
-
-    // create the build dir
-    tracing::debug!("making sure the build directories exist");
-    let build_dir = PathBuf::from(BUILD_DIR);
-    if !build_dir.exists() {
-        std::fs::create_dir(&build_dir).expect("Could not create build directory");
-    }
-
-    let build_time = chrono::Utc::now()
-        .with_timezone(&chrono_tz::US::Pacific)
-        .format("%Y-%m-%d %H:%M:%S %Z")
-        .to_string();
-
-    let build_hash = state.context.build_info.hash.clone();
-    let author_name = state.context.me.name.clone();
-    let author_email = state.context.me.email.clone();
+    let renderer = Renderer::new(state)?;
 
     // render the index page
     tracing::debug!("rendering index page");
-    let index_html = hbs.render(
-        "index",
-        &json!({
-            "parent": "base",
-            "build_time": &build_time,
-            "build_hash": &build_hash,
-            "author_email": &author_email,
-            "author_name": &author_name
-        }),
-    )?;
-    std::fs::write(PathBuf::from(format!("{BUILD_DIR}/index.html")), index_html)?;
-
-    // create the blog dir
-    tracing::debug!("making sure the blog directory exists");
-    let blog_dir = PathBuf::from(format!("{BUILD_DIR}/blog"));
-    if !blog_dir.exists() {
-        std::fs::create_dir(&blog_dir).expect("Could not create blog directory");
-    }
+    renderer.page("index", "", json!({}))?;
 
     // render the blog index
-    tracing::debug!("rendering blog index page");
-    let blog_index_html = hbs.render(
-        "blog_index",
-        &json!({
-            "parent": "base",
-            "posts": &posts,
-            "build_time": &build_time,
-            "build_hash": &build_hash,
-            "author_email": &author_email,
-            "author_name": &author_name
-        }),
-    )?;
-    std::fs::write(
-        PathBuf::from(format!("{BUILD_DIR}/blog/index.html")),
-        blog_index_html,
-    )?;
+    tracing::debug!("rendering blog index pages");
+    let years = archive::years(&posts);
+    for page in pagination::paginate("blog", &posts, state.config().page_size) {
+        renderer.page(
+            "blog_index",
+            &page.dir,
+            json!({
+                "posts": page.items,
+                "pagination": page.pagination,
+                "years": &years,
+            }),
+        )?;
+    }
 
     // render the posts
     tracing::debug!("rendering blog posts");
-    for post in posts {
-        // create the post directory
-        let post_dir = PathBuf::from(format!("{BUILD_DIR}/blog/{}", post.filename));
-        std::fs::create_dir_all(&post_dir)?;
-
-        // render the html
-        tracing::debug!("rendering article: {}", post.filename);
-        let html = hbs.render(
+    for post in &posts {
+        tracing::debug!("rendering article: {}", post.slug);
+        renderer.page(
             "post",
-            &json!({
-                "parent": "base",
+            format!("blog/{}", post.slug),
+            json!({
                 "title": &post.title,
                 "content": &post.content,
-                "build_time": &build_time,
-                "build_hash": &build_hash,
-                "author_email": &author_email,
-                "author_name": &author_name
+                "toc": &post.toc,
+                "description": post.description.as_ref().unwrap_or(&post.excerpt),
+                "reading_time_minutes": post.reading_time_minutes,
+                "cover_image": &post.cover_image,
+                "category": &post.category,
+                "tags": &post.tags,
+                "series": &post.series,
+                "backlinks": &post.backlinks,
+                "related": &post.related,
             }),
         )?;
+    }
+
+    // render the category and tag pages
+    tracing::debug!("rendering taxonomy pages");
+    taxonomy::render(&renderer, Taxonomy::Categories, &posts)?;
+    taxonomy::render(&renderer, Taxonomy::Tags, &posts)?;
+
+    // render the series landing pages
+    tracing::debug!("rendering series pages");
+    series::render(&renderer, &series)?;
 
-        // save the html
-        let build_path = PathBuf::from(format!("{BUILD_DIR}/blog/{}/index.html", post.filename));
-        std::fs::write(&build_path, html)?;
+    // render the year and month archives
+    tracing::debug!("rendering archive pages");
+    archive::render(&renderer, &posts)?;
+
+    // render the feeds
+    tracing::debug!("rendering feeds");
+    write_feeds(state, &posts)?;
+
+    Ok(())
+}
+
+/// Writes the blog feed and one feed per category
+fn write_feeds(state: &AppState, posts: &[Post]) -> Result<(), WebError> {
+    let site_title = &state.context.site.title;
+    let build_dir = &state.config().out;
+    feeds::Feed::new(site_title.clone(), "/blog".to_string(), posts)
+        .write(&build_dir.join("blog"), &state.context)?;
+
+    let categories = Taxonomy::Categories;
+    for (slug, category_posts) in taxonomy::group(categories, posts) {
+        let name = category_posts[0]
+            .category
+            .as_ref()
+            .map_or(slug, |c| c.name.as_str());
+        feeds::Feed::new(
+            format!("{site_title} - {name}"),
+            format!("{}{slug}", categories.url()),
+            category_posts,
+        )
+        .write(
+            &build_dir.join("blog").join(categories.name()).join(slug),
+            &state.context,
+        )?;
     }
 
     Ok(())
 }
 
 /// Copies the assets directory to the build directory
-/// Copies the assets directory to the build directory
-fn copy_static_assets(_state: &AppState) -> Result<(), WebError> {
-    let assets_dir = PathBuf::from("assets");
-    let build_assets_dir = PathBuf::from(format!("{BUILD_DIR}/assets"));
+///
+/// Top-level assets also get a fingerprinted copy, listed in the manifest the
+/// `asset` template helper reads. Tells whether the manifest changed
+fn copy_static_assets(state: &AppState) -> Result<bool, WebError> {
+    let assets_dir = &state.config().assets;
+    let build_assets_dir = state.config().out.join("assets");
+    create_dir(&build_assets_dir)?;
+    let mut manifest = Manifest::default();
+
+    // The code highlighting theme is generated rather than copied
+    manifest.publish(
+        highlight::THEME_FILE,
+        highlight::theme_css().as_bytes(),
+        &build_assets_dir,
+    )?;
 
-    if !assets_dir.exists() {
-        // No assets to copy, bail out
+    if assets_dir.exists() {
+        manifest.publish_dir(assets_dir, &build_assets_dir)?;
+    } else {
         tracing::debug!("No assets directory found, skipping");
-        return Ok(());
     }
 
-    std::fs::create_dir_all(&build_assets_dir)?;
-
-    // Recursive function to copy directory contents
-    fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), WebError> {
-        for entry in std::fs::read_dir(src)? {
-            let entry = entry?;
-            let path = entry.path();
-            let dest_path = dst.join(path.file_name().unwrap());
-
-            if path.is_dir() {
-                std::fs::create_dir_all(&dest_path)?;
-                copy_dir_recursive(&path, &dest_path)?;
-            } else {
-                std::fs::copy(&path, &dest_path)?;
-            }
+    manifest.write(&state.config().out)
+}
+
+/// Copies the contents of a directory into another, recursively
+fn copy_dir(src: &Path, dst: &Path) -> Result<(), WebError> {
+    for entry in std::fs::read_dir(src).map_err(WebError::io_at(src))? {
+        let entry = entry.map_err(WebError::io_at(src))?;
+        let path = entry.path();
+        let dest_path = dst.join(path.file_name().unwrap());
+
+        if path.is_dir() {
+            create_dir(&dest_path)?;
+            copy_dir(&path, &dest_path)?;
+        } else {
+            std::fs::copy(&path, &dest_path).map_err(WebError::io_at(&path))?;
         }
-        Ok(())
     }
+    Ok(())
+}
 
-    copy_dir_recursive(&assets_dir, &build_assets_dir)?;
+/// Creates a directory and its parents if they don't exist yet
+fn create_dir(path: &Path) -> Result<(), WebError> {
+    std::fs::create_dir_all(path).map_err(WebError::io_at(path))
+}
 
-    Ok(())
+/// Writes a file in the build directory
+fn write_file(path: &Path, contents: impl AsRef<[u8]>) -> Result<(), WebError> {
+    std::fs::write(path, contents).map_err(WebError::io_at(path))
 }
 
 /// Builds the static parts of the website
 pub fn build(state: &AppState) -> Result<(), WebError> {
     // copy static assets
-    copy_static_assets(&state)?;
+    copy_static_assets(state)?;
 
     // prerender static content
-    prerender(&state)?;
+    prerender(state)?;
 
     Ok(())
 }
@@ -0,0 +1,132 @@
+use crate::{AppState, BuildOptions, copy_static_assets, errors::WebError, prerender};
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use context::SneakyContext;
+use notify::{RecursiveMode, Watcher};
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::{Stream, StreamExt, wrappers::BroadcastStream};
+
+/// Route the browser client listens on for reload events
+pub const LIVE_RELOAD_PATH: &str = "/__livereload";
+
+/// How long to wait for a burst of file events to settle before rebuilding
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// What part of the site a batch of file changes affects
+#[derive(Default)]
+struct Changes {
+    assets: bool,
+    content: bool,
+}
+
+impl Changes {
+    /// Classifies a changed path by the source directory it lives in
+    fn record(&mut self, path: &Path, assets_dir: &Path) {
+        let path = canonical(path);
+        if path.starts_with(assets_dir) {
+            self.assets = true;
+        } else {
+            self.content = true;
+        }
+    }
+}
+
+/// Canonicalizes a path so it can be compared with event paths
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Server-sent event stream telling open pages to reload after a rebuild
+pub(crate) async fn events(
+    State(reload): State<broadcast::Sender<()>>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(reload.subscribe())
+        .filter_map(|msg| msg.ok())
+        .map(|_| Ok(Event::default().event("reload").data("reload")));
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+/// Watches the source directories, rebuilding and notifying clients on change
+///
+/// The returned watcher must be kept alive for as long as changes should be picked up
+pub(crate) fn watch(
+    context: SneakyContext,
+    options: BuildOptions,
+    reload: broadcast::Sender<()>,
+) -> Result<notify::RecommendedWatcher, WebError> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let mut watcher =
+        notify::recommended_watcher(move |res: notify::Result<notify::Event>| match res {
+            Ok(event) if !event.kind.is_access() => {
+                let _ = tx.send(event.paths);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("file watcher error: {e}"),
+        })?;
+
+    let config = &context.web;
+    for dir in [&config.posts, &config.templates, &config.assets] {
+        if dir.exists() {
+            tracing::debug!("watching {} for changes", dir.display());
+            watcher.watch(dir, RecursiveMode::Recursive)?;
+        }
+    }
+
+    let assets_dir = canonical(&config.assets);
+
+    tokio::spawn(async move {
+        while let Some(paths) = rx.recv().await {
+            // collect the burst of events an editor save produces
+            let mut changes = Changes::default();
+            paths.iter().for_each(|p| changes.record(p, &assets_dir));
+            while let Ok(Some(paths)) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {
+                paths.iter().for_each(|p| changes.record(p, &assets_dir));
+            }
+
+            let context = context.clone();
+            let result =
+                tokio::task::spawn_blocking(move || rebuild(context, options, changes)).await;
+            match result {
+                Ok(Ok(())) => {
+                    // nobody listening is fine, there just aren't any open tabs
+                    let _ = reload.send(());
+                }
+                Ok(Err(e)) => tracing::error!("Error rebuilding site: {e}"),
+                Err(e) => tracing::error!("Rebuild task failed: {e}"),
+            }
+        }
+    });
+
+    Ok(watcher)
+}
+
+/// Rebuilds only the parts of the site affected by the changes
+fn rebuild(
+    context: SneakyContext,
+    options: BuildOptions,
+    changes: Changes,
+) -> Result<(), WebError> {
+    let state = AppState::new(context, options)?;
+
+    // Pages link the fingerprinted assets, so they follow the manifest
+    let mut manifest_changed = false;
+    if changes.assets {
+        tracing::info!("assets changed, copying static assets");
+        manifest_changed = copy_static_assets(&state)?;
+    }
+
+    if changes.content || manifest_changed {
+        tracing::info!("content or asset names changed, re-rendering pages");
+        prerender(&state)?;
+    }
+
+    Ok(())
+}
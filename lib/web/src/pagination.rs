@@ -0,0 +1,69 @@
+use serde::Serialize;
+
+/// Position of a page within a paginated listing
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct Pagination {
+    /// One-based page number
+    pub current: usize,
+    pub total: usize,
+    pub previous: Option<String>,
+    pub next: Option<String>,
+}
+
+/// A single page of a paginated listing
+pub(crate) struct Page<'a, T> {
+    /// Directory of the page, relative to the build directory
+    pub dir: String,
+    pub items: &'a [T],
+    pub pagination: Pagination,
+}
+
+/// Splits items into pages, the first at `{base}/` and the rest at `{base}/page/{n}/`
+///
+/// A page size of 0 puts every item on a single page
+pub(crate) fn paginate<'a, T>(base: &str, items: &'a [T], page_size: usize) -> Vec<Page<'a, T>> {
+    let page_size = if page_size == 0 {
+        items.len().max(1)
+    } else {
+        page_size
+    };
+
+    // always render at least the first page, even when empty
+    let chunks: Vec<&[T]> = if items.is_empty() {
+        vec![items]
+    } else {
+        items.chunks(page_size).collect()
+    };
+    let total = chunks.len();
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, items)| {
+            let current = i + 1;
+            Page {
+                dir: page_dir(base, current),
+                items,
+                pagination: Pagination {
+                    current,
+                    total,
+                    previous: (current > 1).then(|| page_url(base, current - 1)),
+                    next: (current < total).then(|| page_url(base, current + 1)),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Directory of a page relative to the build directory
+fn page_dir(base: &str, page: usize) -> String {
+    match page {
+        1 => base.to_string(),
+        n => format!("{base}/page/{n}"),
+    }
+}
+
+/// Site-relative url of a page
+fn page_url(base: &str, page: usize) -> String {
+    format!("/{}/", page_dir(base, page))
+}
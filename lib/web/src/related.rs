@@ -0,0 +1,121 @@
+use crate::{Post, PostLink};
+use articles::render::{self as markdown, Block, Link};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+/// Number of related posts shown under a post
+const RELATED_POSTS: usize = 3;
+
+/// Collects the destinations of every link in a post
+#[derive(Default)]
+pub(crate) struct Links {
+    pub destinations: Vec<String>,
+}
+
+impl markdown::Transform for Links {
+    fn block(&mut self, block: &mut Block) {
+        if let Block::Definition(definition) = block {
+            self.destinations.push(definition.destination.clone());
+        }
+    }
+
+    fn link(&mut self, link: &mut Link) {
+        self.destinations.push(link.destination.clone());
+    }
+}
+
+/// Fills in the backlinks and related posts of every post
+///
+/// Links count when they point at a post's page, whether site-relative or
+/// under the base url of the site
+pub(crate) fn link(posts: &mut [Post], base_url: &str) {
+    // the posts each post links to
+    let index: HashMap<&str, usize> = posts
+        .iter()
+        .enumerate()
+        .map(|(i, post)| (post.url.as_str(), i))
+        .collect();
+    let mut backlinks = vec![Vec::new(); posts.len()];
+    for (from, post) in posts.iter().enumerate() {
+        let targets: HashSet<usize> = post
+            .links
+            .iter()
+            .filter_map(|url| index.get(site_path(url, base_url)?.as_str()).copied())
+            .filter(|to| *to != from)
+            .collect();
+        for to in targets {
+            backlinks[to].push(from);
+        }
+    }
+
+    let related: Vec<Vec<usize>> = (0..posts.len()).map(|i| related(posts, i)).collect();
+    let links = |indices: &[usize], posts: &[Post]| {
+        indices
+            .iter()
+            .map(|i| PostLink::from(&posts[*i]))
+            .collect::<Vec<_>>()
+    };
+    for (i, (back, related)) in backlinks.iter_mut().zip(related).enumerate() {
+        back.sort_unstable();
+        posts[i].backlinks = links(back, posts);
+        posts[i].related = links(&related, posts);
+    }
+}
+
+/// The posts most related to a post, by shared series, category and tags
+///
+/// Posts with the same score keep their order, newest first
+fn related(posts: &[Post], i: usize) -> Vec<usize> {
+    let post = &posts[i];
+    let mut scored: Vec<(usize, usize)> = posts
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != i)
+        .map(|(other, candidate)| (other, score(post, candidate)))
+        .filter(|(_, score)| *score > 0)
+        .collect();
+    scored.sort_by_key(|(_, score)| Reverse(*score));
+
+    scored
+        .into_iter()
+        .take(RELATED_POSTS)
+        .map(|(other, _)| other)
+        .collect()
+}
+
+/// How related two posts are, a shared series counts most and every shared tag once
+fn score(a: &Post, b: &Post) -> usize {
+    let series = match (&a.series, &b.series) {
+        (Some(a), Some(b)) if a.key == b.key => 3,
+        _ => 0,
+    };
+    let category = match (&a.category, &b.category) {
+        (Some(a), Some(b)) if a.slug == b.slug => 2,
+        _ => 0,
+    };
+    let tags = a
+        .tags
+        .iter()
+        .filter(|tag| b.tags.iter().any(|t| t.slug == tag.slug))
+        .count();
+
+    series + category + tags
+}
+
+/// Site-relative path of a link to a page of the site, without any query or fragment
+fn site_path(url: &str, base_url: &str) -> Option<String> {
+    let base_url = base_url.trim_end_matches('/');
+    let path = match url.strip_prefix(base_url) {
+        Some(path) if !base_url.is_empty() && (path.is_empty() || path.starts_with('/')) => path,
+        _ => url,
+    };
+    if !path.starts_with('/') || path.starts_with("//") {
+        return None;
+    }
+
+    let end = path.find(['?', '#']).unwrap_or(path.len());
+    let path = path[..end].trim_end_matches('/');
+    Some(if path.is_empty() { "/" } else { path }.to_string())
+}
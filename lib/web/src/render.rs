@@ -0,0 +1,77 @@
+use crate::{AppState, assets::Manifest, create_dir, errors::WebError, live_reload, write_file};
+use handlebars::Handlebars;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+/// Templates registered from the templates directory, by name and file
+const TEMPLATES: &[(&str, &str)] = &[
+    ("base", "base.hbs"),
+    ("post_list", "post_list.hbs"),
+    ("toc", "toc.hbs"),
+    ("index", "index.hbs"),
+    ("blog_index", "blog.hbs"),
+    ("post", "post.hbs"),
+    ("taxonomy", "taxonomy.hbs"),
+    ("taxonomy_index", "taxonomy_index.hbs"),
+    ("series", "series.hbs"),
+    ("archive", "archive.hbs"),
+];
+
+/// Renders pages into the build directory with the values shared by every page
+pub(crate) struct Renderer<'a> {
+    hbs: Handlebars<'a>,
+    base: Value,
+    out: PathBuf,
+}
+
+impl Renderer<'_> {
+    /// Registers the templates and collects the shared page values
+    pub fn new(state: &AppState) -> Result<Self, WebError> {
+        tracing::debug!("initializing template engine");
+        let mut hbs = Handlebars::new();
+        // partials would otherwise indent every line, including code in <pre>
+        hbs.set_prevent_indent(true);
+        let templates_dir = &state.config().templates;
+        for (name, file) in TEMPLATES {
+            hbs.register_template_file(name, templates_dir.join(file))?;
+        }
+        hbs.register_helper("asset", Box::new(Manifest::load(&state.config().out)?));
+
+        let build_time = chrono::Utc::now()
+            .with_timezone(&chrono_tz::US::Pacific)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string();
+        let live_reload = state
+            .options
+            .live_reload
+            .then_some(live_reload::LIVE_RELOAD_PATH);
+
+        let base = json!({
+            "parent": "base",
+            "build_time": build_time,
+            "build_hash": &state.context.build_info.hash,
+            "author_email": &state.context.me.email,
+            "author_name": &state.context.me.name,
+            "live_reload": live_reload
+        });
+
+        Ok(Self {
+            hbs,
+            base,
+            out: state.config().out.clone(),
+        })
+    }
+
+    /// Renders a template to `{dir}/index.html`, relative to the build directory
+    pub fn page(&self, template: &str, dir: impl AsRef<Path>, data: Value) -> Result<(), WebError> {
+        let mut context = self.base.clone();
+        if let (Some(context), Value::Object(data)) = (context.as_object_mut(), data) {
+            context.extend(data);
+        }
+
+        let html = self.hbs.render(template, &context)?;
+        let page_dir = self.out.join(dir);
+        create_dir(&page_dir)?;
+        write_file(&page_dir.join("index.html"), html)
+    }
+}
@@ -0,0 +1,78 @@
+use crate::{PostLink, errors::WebError, post_url, render::Renderer};
+use articles::{Article, Series, slug::slugify};
+use serde::Serialize;
+use serde_json::json;
+
+/// Where a post sits in its series, for "part N of M" navigation
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct SeriesNav {
+    pub key: String,
+    pub title: String,
+    pub url: String,
+    /// One-based part number
+    pub part: usize,
+    pub total: usize,
+    pub previous: Option<PostLink>,
+    pub next: Option<PostLink>,
+}
+
+/// Site-relative url of a series landing page
+pub(crate) fn series_url(key: &str) -> String {
+    format!("/{}/", series_path(key))
+}
+
+/// Path of a series landing page in the build directory
+fn series_path(key: &str) -> String {
+    format!("blog/series/{}", slugify(key))
+}
+
+/// Builds the series navigation for an article, if it is part of a series
+pub(crate) fn nav(series: &[Series], article: &Article) -> Option<SeriesNav> {
+    series.iter().find_map(|s| {
+        let index = s.index_of(article)?;
+        Some(SeriesNav {
+            key: s.key.to_string(),
+            title: s.title(),
+            url: series_url(s.key),
+            part: index + 1,
+            total: s.parts.len(),
+            previous: index
+                .checked_sub(1)
+                .and_then(|i| s.parts.get(i))
+                .map(|a| PostLink::from(*a)),
+            next: s.parts.get(index + 1).map(|a| PostLink::from(*a)),
+        })
+    })
+}
+
+/// Renders a landing page for every series, listing its parts in order
+pub(crate) fn render(renderer: &Renderer, series: &[Series]) -> Result<(), WebError> {
+    for s in series {
+        tracing::debug!("rendering series page: {}", s.key);
+        let parts: Vec<_> = s
+            .parts
+            .iter()
+            .enumerate()
+            .map(|(i, article)| {
+                json!({
+                    "part": i + 1,
+                    "title": &article.title,
+                    "url": post_url(article),
+                    "date": article.date.format("%B %d, %Y").to_string(),
+                })
+            })
+            .collect();
+
+        renderer.page(
+            "series",
+            series_path(s.key),
+            json!({
+                "title": s.title(),
+                "key": s.key,
+                "parts": parts,
+            }),
+        )?;
+    }
+
+    Ok(())
+}
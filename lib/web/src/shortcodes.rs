@@ -0,0 +1,58 @@
+use crate::errors::WebError;
+use articles::render::{Shortcode, Shortcodes};
+use handlebars::Handlebars;
+use serde_json::{Value, json};
+use std::{fs, path::Path};
+
+/// Directory of the shortcode templates, inside the templates directory
+const SHORTCODES_DIR: &str = "shortcodes";
+
+/// Shortcodes rendered with the templates in `templates/shortcodes/`
+///
+/// `{{< video src="demo.mp4" >}}` renders `video.hbs` with `src` set, and
+/// the content of a paired shortcode is passed as `inner`, already HTML
+pub(crate) struct ShortcodeTemplates {
+    hbs: Handlebars<'static>,
+}
+
+impl ShortcodeTemplates {
+    /// Registers every `.hbs` file of the shortcodes directory, which may not exist
+    pub fn load(templates_dir: &Path) -> Result<Self, WebError> {
+        let mut hbs = Handlebars::new();
+        hbs.set_prevent_indent(true);
+
+        let dir = templates_dir.join(SHORTCODES_DIR);
+        if dir.is_dir() {
+            for entry in fs::read_dir(&dir).map_err(WebError::io_at(&dir))? {
+                let path = entry.map_err(WebError::io_at(&dir))?.path();
+                if path.extension().is_some_and(|e| e == "hbs")
+                    && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+                {
+                    hbs.register_template_file(name, &path)?;
+                }
+            }
+        }
+
+        Ok(Self { hbs })
+    }
+}
+
+impl Shortcodes for ShortcodeTemplates {
+    fn render(&self, shortcode: &Shortcode) -> Result<String, String> {
+        if !self.hbs.has_template(&shortcode.name) {
+            return Err(format!(
+                "no template at {SHORTCODES_DIR}/{}.hbs",
+                shortcode.name
+            ));
+        }
+
+        let mut data = json!(shortcode.args);
+        if let (Value::Object(data), Some(inner)) = (&mut data, &shortcode.inner) {
+            data.insert("inner".to_string(), json!(inner));
+        }
+        self.hbs
+            .render(&shortcode.name, &data)
+            .map(|html| html.trim().to_string())
+            .map_err(|e| e.to_string())
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{Post, errors::WebError, render::Renderer};
+use articles::slug::slugify;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// Slug given to terms that have nothing to slugify
+const FALLBACK_SLUG: &str = "term";
+
+/// A way of grouping posts, each post belongs to zero or more of its terms
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Taxonomy {
+    Categories,
+    Tags,
+}
+
+impl Taxonomy {
+    /// Plural name, used as the url segment and for overview pages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "categories",
+            Taxonomy::Tags => "tags",
+        }
+    }
+
+    /// Singular name, used to label a single term
+    pub fn label(&self) -> &'static str {
+        match self {
+            Taxonomy::Categories => "category",
+            Taxonomy::Tags => "tag",
+        }
+    }
+
+    /// Site-relative url of the overview page
+    pub fn url(&self) -> String {
+        format!("/blog/{}/", self.name())
+    }
+
+    /// The terms of this taxonomy a post belongs to
+    fn terms<'a>(&self, post: &'a Post) -> Vec<&'a Term> {
+        match self {
+            Taxonomy::Categories => post.category.iter().collect(),
+            Taxonomy::Tags => post.tags.iter().collect(),
+        }
+    }
+}
+
+/// A single category or tag, linkable from templates
+#[derive(Serialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Term {
+    pub name: String,
+    pub slug: String,
+    pub url: String,
+}
+
+impl Term {
+    /// Creates the term with the given name in a taxonomy
+    pub fn new(taxonomy: Taxonomy, name: &str) -> Self {
+        let slug = slug(name);
+        Term {
+            name: name.trim().to_string(),
+            url: format!("{}{slug}/", taxonomy.url()),
+            slug,
+        }
+    }
+}
+
+/// Utility for turning a category or tag into a url segment
+pub(crate) fn slug(term: &str) -> String {
+    match slugify(term) {
+        slug if slug.is_empty() => FALLBACK_SLUG.to_string(),
+        slug => slug,
+    }
+}
+
+/// Groups posts by the terms of a taxonomy, ordered by term slug
+pub(crate) fn group(taxonomy: Taxonomy, posts: &[Post]) -> BTreeMap<&str, Vec<&Post>> {
+    let mut groups: BTreeMap<&str, Vec<&Post>> = BTreeMap::new();
+    for post in posts {
+        for term in taxonomy.terms(post) {
+            groups.entry(&term.slug).or_default().push(post);
+        }
+    }
+    groups
+}
+
+/// Renders the overview page and one listing page per term of the taxonomy
+pub(crate) fn render(
+    renderer: &Renderer,
+    taxonomy: Taxonomy,
+    posts: &[Post],
+) -> Result<(), WebError> {
+    let groups = group(taxonomy, posts);
+    let mut terms = Vec::new();
+
+    for (slug, term_posts) in &groups {
+        // every post in the group has the term, the first one names it
+        let Some(term) = taxonomy
+            .terms(term_posts[0])
+            .into_iter()
+            .find(|t| t.slug == *slug)
+        else {
+            continue;
+        };
+
+        tracing::debug!("rendering {} page: {}", taxonomy.label(), term.name);
+        renderer.page(
+            "taxonomy",
+            format!("blog/{}/{slug}", taxonomy.name()),
+            json!({
+                "taxonomy": taxonomy.name(),
+                "taxonomy_url": taxonomy.url(),
+                "label": taxonomy.label(),
+                "term": term,
+                "posts": term_posts,
+            }),
+        )?;
+
+        terms.push(json!({
+            "name": &term.name,
+            "url": &term.url,
+            "count": term_posts.len(),
+        }));
+    }
+
+    tracing::debug!("rendering {} overview page", taxonomy.name());
+    renderer.page(
+        "taxonomy_index",
+        format!("blog/{}", taxonomy.name()),
+        json!({
+            "taxonomy": taxonomy.name(),
+            "label": taxonomy.label(),
+            "terms": terms,
+        }),
+    )
+}
@@ -1,3 +1,4 @@
+use crate::errors::CliError;
 use clap::Command;
 use context::SneakyContext;
 
@@ -7,7 +8,8 @@ pub(crate) fn cli() -> Command {
 }
 
 /// Runs the serve subcommand
-pub(crate) async fn run(_matches: &clap::ArgMatches, ctx: &SneakyContext) {
+pub(crate) async fn run(_matches: &clap::ArgMatches, ctx: &SneakyContext) -> Result<(), CliError> {
     println!("A cli tool for interacting with sneaky crow data");
-    println!("by {} <{}>", ctx.me.name, ctx.me.email)
+    println!("by {} <{}>", ctx.me.name, ctx.me.email);
+    Ok(())
 }
@@ -1,9 +1,9 @@
+use crate::errors::CliError;
 use articles::Article;
 use clap::{Command, arg};
+use context::SneakyContext;
 use std::path::PathBuf;
 
-const DEFAULT_ARTICLE_OUTPUT_DIR: &str = "_posts/";
-
 /// Create the command to interact with articles
 pub(crate) fn cli() -> Command {
     Command::new("article")
@@ -13,34 +13,60 @@ pub(crate) fn cli() -> Command {
         .subcommand(
             Command::new("create")
                 .about("Creates a new article file with the given data")
-                .arg(arg!(output: -o --output <OUTPUT> "optional output of the file"))
-                .arg(arg!(-t --title <TITLE> "title of the article")),
+                .arg(
+                    arg!(output: -o --output <OUTPUT> "optional output of the file, defaults to the posts directory"),
+                )
+                .arg(arg!(-t --title <TITLE> "title of the article"))
+                .arg(arg!(--slug <SLUG> "url slug of the article, derived from the title by default"))
+                .arg(arg!(-c --category <CATEGORY> "category of the article"))
+                .arg(arg!(--tag <TAG> ... "tag for the article, can be repeated"))
+                .arg(arg!(-s --summary <SUMMARY> "short summary of the article"))
+                .arg(arg!(-d --draft "mark the article as a draft")),
         )
 }
 
 /// Runs the article with given arg matches
-pub(crate) fn run(matches: &clap::ArgMatches) {
+pub(crate) fn run(matches: &clap::ArgMatches, ctx: &SneakyContext) -> Result<(), CliError> {
     match matches.subcommand() {
         Some(("create", sub_matches)) => {
             // Get the title
             let title = sub_matches.get_one::<String>("title").cloned();
+            let slug = sub_matches.get_one::<String>("slug").cloned();
+
+            // Get the optional metadata
+            let category = sub_matches.get_one::<String>("category").cloned();
+            let summary = sub_matches.get_one::<String>("summary").cloned();
+            let tags = sub_matches
+                .get_many::<String>("tag")
+                .map(|tags| tags.cloned().collect())
+                .unwrap_or_default();
 
-            // Get the output, or fallback to the default
+            // Get the output, or fallback to the configured posts directory
             let output = sub_matches
                 .get_one::<String>("output")
-                .map(|o| PathBuf::from(o))
-                .unwrap_or(PathBuf::from(DEFAULT_ARTICLE_OUTPUT_DIR));
+                .map(PathBuf::from)
+                .unwrap_or_else(|| ctx.web.posts.clone());
 
             // Make sure the output directory exists
-            std::fs::create_dir_all(output.clone()).expect("Could not create output directory");
+            std::fs::create_dir_all(&output).map_err(|source| CliError::IO {
+                path: output.clone(),
+                source,
+            })?;
 
             // Build the article and save it
             Article::builder()
                 .maybe_title(title)
+                .maybe_slug(slug)
+                .maybe_category(category)
+                .maybe_summary(summary)
+                .tags(tags)
+                .draft(sub_matches.get_flag("draft"))
                 .build()
-                .save(&output)
-                .expect("Could not save article");
+                .save(&output)?;
+
+            Ok(())
         }
-        _ => unreachable!(),
+        Some((name, _)) => Err(CliError::UnknownCommand(format!("article {name}"))),
+        None => Err(CliError::UnknownCommand("article".to_string())),
     }
 }
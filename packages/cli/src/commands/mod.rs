@@ -2,6 +2,7 @@ pub(crate) mod about;
 pub(crate) mod article;
 pub(crate) mod web;
 
+use crate::errors::CliError;
 use clap::Command;
 use context::{DEFAULT_CONFIG_FILE, SneakyContext};
 
@@ -11,14 +12,23 @@ pub(crate) struct Cli {
 }
 
 impl Cli {
+    /// Creates the CLI with every subcommand, loading the context from the config file
+    pub(crate) fn new() -> Result<Self, CliError> {
+        Ok(Self {
+            subcommands: vec![article::cli(), web::cli(), about::cli()],
+            context: SneakyContext::load(DEFAULT_CONFIG_FILE)?,
+        })
+    }
+
     /// Parses and runs the CLI with default parameters
-    pub(crate) async fn parse(&self) {
+    pub(crate) async fn parse(&self) -> Result<(), CliError> {
         let matches = self.build().get_matches();
         match matches.subcommand() {
-            Some(("article", sub_matches)) => article::run(sub_matches),
-            Some(("web", sub_matches)) => web::run(sub_matches).await,
+            Some(("article", sub_matches)) => article::run(sub_matches, &self.context),
+            Some(("web", sub_matches)) => web::run(sub_matches, &self.context).await,
             Some(("about", sub_matches)) => about::run(sub_matches, &self.context).await,
-            _ => unreachable!(),
+            Some((name, _)) => Err(CliError::UnknownCommand(name.to_string())),
+            None => Err(CliError::UnknownCommand(String::new())),
         }
     }
 
@@ -39,12 +49,3 @@ impl Cli {
         root_cmd
     }
 }
-
-impl Default for Cli {
-    fn default() -> Self {
-        Self {
-            subcommands: vec![article::cli(), web::cli(), about::cli()],
-            context: SneakyContext::from_file(DEFAULT_CONFIG_FILE).unwrap_or_default(),
-        }
-    }
-}
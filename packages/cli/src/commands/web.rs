@@ -1,4 +1,8 @@
-use clap::Command;
+use crate::errors::CliError;
+use clap::{Arg, Command, arg, value_parser};
+use context::SneakyContext;
+use std::path::PathBuf;
+use web::{AppState, BuildOptions};
 
 /// Create the command to interact with articles
 pub(crate) fn cli() -> Command {
@@ -6,23 +10,83 @@ pub(crate) fn cli() -> Command {
         .about("fn for personal website")
         .subcommand_required(true)
         .arg_required_else_help(true)
-        .subcommand(Command::new("serve").about("Serves the website locally"))
-        .subcommand(Command::new("build").about("Builds the website"))
+        .subcommand(
+            Command::new("serve")
+                .about("Serves the website locally")
+                .arg(arg!(--host <HOST> "host to bind the server to"))
+                .arg(arg!(--port <PORT> "port to listen on").value_parser(value_parser!(u16)))
+                .args(build_args()),
+        )
+        .subcommand(
+            Command::new("build")
+                .about("Builds the website")
+                .args(build_args()),
+        )
+}
+
+/// Arguments shared by every subcommand that builds the site
+fn build_args() -> Vec<Arg> {
+    vec![
+        arg!(--drafts "include draft articles"),
+        arg!(--future "include articles scheduled for the future"),
+        arg!(--out <DIR> "directory to build the site into").value_parser(value_parser!(PathBuf)),
+        arg!(--posts <DIR> "directory to read articles from").value_parser(value_parser!(PathBuf)),
+        arg!(--templates <DIR> "directory containing the templates")
+            .value_parser(value_parser!(PathBuf)),
+        arg!(--assets <DIR> "directory containing the static assets")
+            .value_parser(value_parser!(PathBuf)),
+    ]
+}
+
+/// Reads the build options from the subcommand matches
+fn build_options(matches: &clap::ArgMatches) -> BuildOptions {
+    BuildOptions {
+        drafts: matches.get_flag("drafts"),
+        future: matches.get_flag("future"),
+        ..Default::default()
+    }
+}
+
+/// Applies the command line overrides on top of the `[web]` configuration
+fn web_context(matches: &clap::ArgMatches, ctx: &SneakyContext) -> SneakyContext {
+    let mut ctx = ctx.clone();
+    let config = &mut ctx.web;
+
+    // serve-only arguments aren't defined for build
+    if let Ok(Some(host)) = matches.try_get_one::<String>("host") {
+        config.host = host.clone();
+    }
+    if let Ok(Some(port)) = matches.try_get_one::<u16>("port") {
+        config.port = *port;
+    }
+
+    for (name, dir) in [
+        ("out", &mut config.out),
+        ("posts", &mut config.posts),
+        ("templates", &mut config.templates),
+        ("assets", &mut config.assets),
+    ] {
+        if let Some(value) = matches.get_one::<PathBuf>(name) {
+            *dir = value.clone();
+        }
+    }
+
+    ctx
 }
 
 /// Runs the serve subcommand
-pub(crate) async fn run(matches: &clap::ArgMatches) {
+pub(crate) async fn run(matches: &clap::ArgMatches, ctx: &SneakyContext) -> Result<(), CliError> {
     match matches.subcommand() {
-        Some(("serve", _)) => {
-            if let Err(e) = web::serve().await {
-                tracing::error!("Error running serve command: {e}");
-            }
+        Some(("serve", sub_matches)) => {
+            web::serve(web_context(sub_matches, ctx), build_options(sub_matches)).await?
         }
-        Some(("build", _)) => {
-            if let Err(e) = web::build(&web::AppState::default()) {
-                tracing::error!("Error running build command: {e}");
-            }
+        Some(("build", sub_matches)) => {
+            let state = AppState::new(web_context(sub_matches, ctx), build_options(sub_matches))?;
+            web::build(&state)?;
         }
-        _ => unreachable!(),
+        Some((name, _)) => return Err(CliError::UnknownCommand(format!("web {name}"))),
+        None => return Err(CliError::UnknownCommand("web".to_string())),
     }
+
+    Ok(())
 }
@@ -0,0 +1,71 @@
+use articles::errors::ArticleError;
+use context::SneakyContextError;
+use std::path::PathBuf;
+use thiserror::Error;
+use web::errors::WebError;
+
+/// Exit codes, following the BSD sysexits conventions
+const EXIT_USAGE: u8 = 64;
+const EXIT_DATA: u8 = 65;
+const EXIT_UNAVAILABLE: u8 = 69;
+const EXIT_SOFTWARE: u8 = 70;
+const EXIT_IO: u8 = 74;
+const EXIT_CONFIG: u8 = 78;
+
+/// Top level error for every command of the CLI
+#[derive(Error, Debug)]
+pub(crate) enum CliError {
+    #[error("{0}")]
+    Article(#[from] ArticleError),
+    #[error("{0}")]
+    Web(#[from] WebError),
+    #[error("Could not load configuration: {0}")]
+    Context(#[from] SneakyContextError),
+    #[error("I/O error at {}: {source}", path.display())]
+    IO {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Unknown command: {0}")]
+    UnknownCommand(String),
+}
+
+impl CliError {
+    /// The process exit code scripts can use to tell failures apart
+    pub(crate) fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Article(e) => article_exit_code(e),
+            CliError::Web(e) => match e {
+                WebError::ArticlesLoad(e) | WebError::ArticleRender(e) => article_exit_code(e),
+                WebError::Context(_) => EXIT_CONFIG,
+                WebError::MissingImage { .. } | WebError::Image { .. } => EXIT_DATA,
+                WebError::Bind { .. } => EXIT_UNAVAILABLE,
+                WebError::IO(_) | WebError::PathIO { .. } | WebError::Watch(_) => EXIT_IO,
+                WebError::Serve(_)
+                | WebError::Render(_)
+                | WebError::Template(_)
+                | WebError::Json(_) => EXIT_SOFTWARE,
+            },
+            CliError::Context(_) => EXIT_CONFIG,
+            CliError::IO { .. } => EXIT_IO,
+            CliError::UnknownCommand(_) => EXIT_USAGE,
+        }
+    }
+}
+
+/// Exit code for article failures, separating bad content from I/O
+fn article_exit_code(error: &ArticleError) -> u8 {
+    match error {
+        ArticleError::InFile { source, .. } => article_exit_code(source),
+        ArticleError::IO(_) | ArticleError::PathIO { .. } => EXIT_IO,
+        ArticleError::FrontMatterParse(_)
+        | ArticleError::MissingFrontMatter
+        | ArticleError::UnclosedFrontMatter { .. }
+        | ArticleError::FrontMatterSyntax { .. }
+        | ArticleError::ContentParse(_)
+        | ArticleError::Shortcode { .. }
+        | ArticleError::Math { .. }
+        | ArticleError::Link { .. } => EXIT_DATA,
+    }
+}
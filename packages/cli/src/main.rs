@@ -1,10 +1,12 @@
 pub(crate) mod commands;
+pub(crate) mod errors;
 
 pub(crate) use commands::Cli;
+use std::process::ExitCode;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
-async fn main() {
+async fn main() -> ExitCode {
     // Start the tracer
     tracing_subscriber::registry()
         .with(
@@ -13,5 +15,16 @@ async fn main() {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
-    Cli::default().parse().await;
+    let result = match Cli::new() {
+        Ok(cli) => cli.parse().await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(e.exit_code())
+        }
+    }
 }